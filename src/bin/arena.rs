@@ -0,0 +1,215 @@
+// Plays bots against each other and reports the results.
+//
+//     arena --black random --white mcts:playouts=500 --games 20 --size 9 --seed 42
+//
+// Every game gets its own seed, printed with its result, which is given to both bots. Rerunning a
+// game with `--replay-seed <seed>` and the same bots plays the same moves again as long as the
+// bots do not search against the clock. Searches that stop after a time limit, such as `mcts`
+// and `solver` with their default `time`, or that run on several threads, depend on how fast the
+// machine is. Give them a large `time` so that `playouts` or `depth` ends the search, and
+// `threads=1`, e.g. `mcts:playouts=500,time=1000,threads=1`.
+// `--sgf-dir <directory>` saves every game, a replayed game is printed as SGF if it is not given.
+// `--book <book file>` lets both bots play from an opening book made by `build_book`.
+// `--patterns <weights file>` gives the bots with a `patterns` option those weights for their
+// `patterns` playout policy, as learned by `learn_patterns`, unless the spec sets its own.
+
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use badukrs::bot::{default_registry, parse_bot_spec, BotError, BotRegistry, DynGameBot};
+use badukrs::cli::arg_value;
+use badukrs::game::{BadukClassical, GameResult, GameTree, Player, DEFAULT_KOMI};
+use badukrs::opening_book::{BookBot, OpeningBook};
+use badukrs::search::SearchContext;
+use badukrs::sgf;
+
+struct ArenaSettings {
+    black: String,
+    white: String,
+    games: u64,
+    seed: u64,
+    komi: f32,
+    sgf_dir: Option<PathBuf>,
+    book: Option<PathBuf>,
+    patterns: Option<PathBuf>,
+    replay: bool,
+}
+
+fn main() {
+    let registry = default_registry::<BadukClassical<19>>();
+    let Some(settings) = settings_from_args() else {
+        eprintln!(
+            "Usage: arena --black <bot> --white <bot> [--games <n>] [--size 5|7|9|13|19] \
+             [--seed <seed> | --replay-seed <seed>] [--komi <komi>] [--sgf-dir <directory>] \
+             [--book <book file>] [--patterns <weights file>]"
+        );
+        eprintln!(
+            "Replayed games only repeat for bots limited by playouts or depth, e.g. \
+             mcts:playouts=500,time=1000,threads=1"
+        );
+        let names: Vec<_> = registry.entries().iter().map(|entry| entry.name).collect();
+        eprintln!("Bots: {}", names.join(", "));
+        std::process::exit(2);
+    };
+
+    let size = arg_value("--size").unwrap_or_else(|| "19".to_string());
+    let result = match size.as_str() {
+        "5" => run_arena::<5>(&settings),
+        "7" => run_arena::<7>(&settings),
+        "9" => run_arena::<9>(&settings),
+        "13" => run_arena::<13>(&settings),
+        "19" => run_arena::<19>(&settings),
+        _ => {
+            eprintln!("Unsupported board size {size}, use 5, 7, 9, 13 or 19");
+            std::process::exit(2);
+        }
+    };
+    if let Err(err) = result {
+        eprintln!("{err}");
+        std::process::exit(1);
+    }
+}
+
+fn settings_from_args() -> Option<ArenaSettings> {
+    let replay_seed = arg_value("--replay-seed").and_then(|seed| seed.parse().ok());
+    let seed = replay_seed
+        .or_else(|| arg_value("--seed").and_then(|seed| seed.parse().ok()))
+        .unwrap_or_else(rand::random);
+    Some(ArenaSettings {
+        black: arg_value("--black")?,
+        white: arg_value("--white")?,
+        games: match replay_seed {
+            Some(_) => 1,
+            None => arg_value("--games").map_or(Some(1), |games| games.parse().ok())?,
+        },
+        seed,
+        komi: arg_value("--komi").map_or(Some(DEFAULT_KOMI), |komi| komi.parse().ok())?,
+        sgf_dir: arg_value("--sgf-dir").map(PathBuf::from),
+        book: arg_value("--book").map(PathBuf::from),
+        patterns: arg_value("--patterns").map(PathBuf::from),
+        replay: replay_seed.is_some(),
+    })
+}
+
+/// Creates the bot for one game, seeded with the game seed and using the pattern weights unless
+/// the spec sets its own, and playing from the book if there is one.
+fn create_seeded_bot<const SIZE: usize>(
+    registry: &BotRegistry<BadukClassical<SIZE>>,
+    spec: &str,
+    seed: u64,
+    book: Option<&Arc<OpeningBook<SIZE>>>,
+    patterns: Option<&Path>,
+) -> Result<Arc<dyn DynGameBot<BadukClassical<SIZE>>>, BotError> {
+    let (name, mut options) = parse_bot_spec(spec)?;
+    options
+        .entry("seed".to_string())
+        .or_insert_with(|| seed.to_string());
+    let has_patterns = registry.get(name).is_some_and(|entry| {
+        entry
+            .default_options()
+            .iter()
+            .any(|(option, _)| *option == "patterns")
+    });
+    if let Some(path) = patterns.filter(|_| has_patterns) {
+        options
+            .entry("patterns".to_string())
+            .or_insert_with(|| path.display().to_string());
+    }
+    let bot = registry.create(name, &options)?;
+    let Some(book) = book else {
+        return Ok(bot);
+    };
+    let mut book_bot = BookBot::new(bot, book.clone());
+    book_bot.seed = Some(seed);
+    Ok(Arc::new(book_bot))
+}
+
+fn run_arena<const SIZE: usize>(
+    settings: &ArenaSettings,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let registry = default_registry::<BadukClassical<SIZE>>();
+    let book = match &settings.book {
+        Some(path) => Some(Arc::new(OpeningBook::<SIZE>::load(path)?)),
+        None => None,
+    };
+    let (mut black_wins, mut white_wins, mut other) = (0, 0, 0);
+
+    for game_index in 0..settings.games {
+        let seed = settings.seed.wrapping_add(game_index);
+        let patterns = settings.patterns.as_deref();
+        let black = create_seeded_bot(&registry, &settings.black, seed, book.as_ref(), patterns)?;
+        let white = create_seeded_bot(&registry, &settings.white, seed, book.as_ref(), patterns)?;
+        let tree = play_game(black.as_ref(), white.as_ref(), settings.komi);
+
+        let result = tree.info.result.unwrap_or(GameResult::Void);
+        match result.winner() {
+            Some(Player::Black) => black_wins += 1,
+            Some(Player::White) => white_wins += 1,
+            None => other += 1,
+        }
+        println!(
+            "Game {}: seed {seed}, {result}, {} moves",
+            game_index + 1,
+            tree.main_line().count()
+        );
+
+        let record = sgf::write(&[sgf::from_game_tree(&tree)]);
+        match &settings.sgf_dir {
+            Some(directory) => save_record(directory, seed, &record)?,
+            None if settings.replay => println!("{record}"),
+            None => {}
+        }
+    }
+
+    println!(
+        "Black ({}) {black_wins}, White ({}) {white_wins}, other {other}",
+        settings.black, settings.white
+    );
+    Ok(())
+}
+
+fn save_record(directory: &Path, seed: u64, record: &str) -> std::io::Result<()> {
+    std::fs::create_dir_all(directory)?;
+    std::fs::write(directory.join(format!("arena-{seed}.sgf")), record)
+}
+
+/// Plays one game to the end. Games that run far longer than a normal game are left without a
+/// result.
+fn play_game<const SIZE: usize>(
+    black: &dyn DynGameBot<BadukClassical<SIZE>>,
+    white: &dyn DynGameBot<BadukClassical<SIZE>>,
+    komi: f32,
+) -> GameTree<BadukClassical<SIZE>> {
+    let mut tree = GameTree::new(BadukClassical::new());
+    tree.info.komi = komi;
+    tree.info.black_player = Some("arena black".to_string());
+    tree.info.white_player = Some("arena white".to_string());
+
+    let mut game = tree.root().game.clone();
+    let mut node = tree.root_mut();
+    let max_moves = SIZE * SIZE * 3;
+    for move_number in 0..max_moves {
+        if game.is_game_over() {
+            break;
+        }
+        let context = SearchContext {
+            move_number,
+            komi,
+            ..SearchContext::new(SIZE)
+        };
+        let bot = match game.turn {
+            Player::Black => black,
+            Player::White => white,
+        };
+        let Ok(mv) = bot.select_move(&game, &context) else {
+            break;
+        };
+        let Ok(next) = node.make_move(mv) else {
+            break;
+        };
+        game = next.game.clone();
+        node = next;
+    }
+    tree.info.result = game.result(komi);
+    tree
+}
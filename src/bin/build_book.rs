@@ -0,0 +1,60 @@
+// Builds an opening book from a directory of SGF games.
+//
+//     build_book --games <directory> --out <book file> [--size 9|13|19] [--moves 30]
+//
+// The first `--moves` moves of every game's main line go into the book, games on other board
+// sizes are skipped. Bots play from the book with `--book <book file>`.
+
+use std::path::Path;
+
+use badukrs::cli::{arg_value, path_from_args};
+use badukrs::opening_book::{OpeningBook, OpeningBookError};
+
+const DEFAULT_BOOK_MOVES: usize = 30;
+
+fn main() {
+    let (Some(games), Some(out)) = (path_from_args("--games"), path_from_args("--out")) else {
+        eprintln!(
+            "Usage: build_book --games <directory> --out <book file> [--size 9|13|19] \
+             [--moves <n>]"
+        );
+        std::process::exit(2);
+    };
+    let Some(max_moves) =
+        arg_value("--moves").map_or(Some(DEFAULT_BOOK_MOVES), |moves| moves.parse().ok())
+    else {
+        eprintln!("--moves takes a number of moves");
+        std::process::exit(2);
+    };
+
+    let size = arg_value("--size").unwrap_or_else(|| "19".to_string());
+    let result = match size.as_str() {
+        "9" => build::<9>(&games, &out, max_moves),
+        "13" => build::<13>(&games, &out, max_moves),
+        "19" => build::<19>(&games, &out, max_moves),
+        _ => {
+            eprintln!("Unsupported board size {size}, use 9, 13 or 19");
+            std::process::exit(2);
+        }
+    };
+    if let Err(err) = result {
+        eprintln!("{err}");
+        std::process::exit(1);
+    }
+}
+
+fn build<const SIZE: usize>(
+    games: &Path,
+    out: &Path,
+    max_moves: usize,
+) -> Result<(), OpeningBookError> {
+    let book = OpeningBook::<SIZE>::from_sgf_directory(games, max_moves)?;
+    book.save(out)?;
+    println!(
+        "{} positions from {} games written to {}",
+        book.len(),
+        book.games,
+        out.display()
+    );
+    Ok(())
+}
@@ -0,0 +1,112 @@
+// Summarises a directory of SGF games.
+//
+//     collection_stats --games <directory> [--format table|json] [--top 10] [--opening-moves 4]
+//
+// Reports the results of every player, the average game length, how games ended, the most
+// common first moves and openings, and which komi, rules and board sizes were played. Games on
+// board sizes other than 9, 13 and 19 and files that cannot be read are skipped with a warning.
+
+use std::path::{Path, PathBuf};
+
+use badukrs::cli::arg_value;
+use badukrs::collection_stats::CollectionStats;
+use badukrs::sgf::{self, SgfNode};
+
+const DEFAULT_TOP: usize = 10;
+const DEFAULT_OPENING_MOVES: usize = 4;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Format {
+    Table,
+    Json,
+}
+
+struct StatsSettings {
+    games: PathBuf,
+    format: Format,
+    top: usize,
+    opening_moves: usize,
+}
+
+fn main() {
+    let Some(settings) = settings_from_args() else {
+        eprintln!(
+            "Usage: collection_stats --games <directory> [--format table|json] [--top <n>] \
+             [--opening-moves <n>]"
+        );
+        std::process::exit(2);
+    };
+    let stats = match collect(&settings.games, settings.opening_moves) {
+        Ok(stats) => stats,
+        Err(err) => {
+            eprintln!("Could not read {}: {err}", settings.games.display());
+            std::process::exit(1);
+        }
+    };
+    match settings.format {
+        Format::Table => print!("{}", stats.to_table(settings.top)),
+        Format::Json => print!("{}", stats.to_json(settings.top)),
+    }
+}
+
+fn settings_from_args() -> Option<StatsSettings> {
+    Some(StatsSettings {
+        games: arg_value("--games").map(PathBuf::from)?,
+        format: match arg_value("--format").as_deref() {
+            None | Some("table") => Format::Table,
+            Some("json") => Format::Json,
+            Some(_) => return None,
+        },
+        top: arg_value("--top").map_or(Some(DEFAULT_TOP), |top| top.parse().ok())?,
+        opening_moves: arg_value("--opening-moves")
+            .map_or(Some(DEFAULT_OPENING_MOVES), |moves| moves.parse().ok())?,
+    })
+}
+
+fn collect(directory: &Path, opening_moves: usize) -> std::io::Result<CollectionStats> {
+    let paths = sgf::sgf_files_in(directory)?;
+
+    let mut stats = CollectionStats::new();
+    for path in paths {
+        let collection = match std::fs::read_to_string(&path)
+            .map_err(|err| err.to_string())
+            .and_then(|contents| sgf::parse(&contents).map_err(|err| err.to_string()))
+        {
+            Ok(collection) => collection,
+            Err(err) => {
+                eprintln!("Skipping {}: {err}", path.display());
+                continue;
+            }
+        };
+        for root in &collection {
+            if let Err(err) = add_game(&mut stats, root, opening_moves) {
+                eprintln!("Skipping a game in {}: {err}", path.display());
+            }
+        }
+    }
+    Ok(stats)
+}
+
+fn add_game(
+    stats: &mut CollectionStats,
+    root: &SgfNode,
+    opening_moves: usize,
+) -> Result<(), String> {
+    let size = root.get("SZ").unwrap_or("19").trim();
+    match size {
+        "9" => add_sized_game::<9>(stats, root, opening_moves),
+        "13" => add_sized_game::<13>(stats, root, opening_moves),
+        "19" => add_sized_game::<19>(stats, root, opening_moves),
+        _ => Err(format!("Unsupported board size {size}")),
+    }
+}
+
+fn add_sized_game<const SIZE: usize>(
+    stats: &mut CollectionStats,
+    root: &SgfNode,
+    opening_moves: usize,
+) -> Result<(), String> {
+    let tree = sgf::to_game_tree::<SIZE>(root).map_err(|err| err.to_string())?;
+    stats.add_game(&tree, opening_moves);
+    Ok(())
+}
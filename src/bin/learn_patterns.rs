@@ -0,0 +1,125 @@
+// Learns playout pattern weights from a directory of SGF games.
+//
+//     learn_patterns --games <directory> --out <weights file> [--size 9|13|19] [--rate 0.05]
+//                    [--passes 1] [--from <weights file>] [--augment]
+//
+// Every move of every game's main line is learned from, `--passes` times over the collection,
+// starting from the default weights or those of `--from`. With `--augment` each move is also
+// learned from in the other seven orientations of the board. Games on other board sizes are
+// skipped.
+// Bots sample playouts by the weights with `policy=patterns,patterns=<weights file>`, or with
+// `policy=patterns` and arena's `--patterns <weights file>`.
+
+use std::path::{Path, PathBuf};
+
+use badukrs::cli::{arg_value, has_flag};
+use badukrs::game::{BadukClassical, GameTree};
+use badukrs::local_patterns::PatternWeights;
+use badukrs::sgf::{self, SgfError};
+
+const DEFAULT_LEARNING_RATE: f32 = 0.05;
+
+struct LearnSettings {
+    games: PathBuf,
+    out: PathBuf,
+    from: Option<PathBuf>,
+    learning_rate: f32,
+    passes: usize,
+    augment: bool,
+}
+
+fn main() {
+    let Some(settings) = settings_from_args() else {
+        eprintln!(
+            "Usage: learn_patterns --games <directory> --out <weights file> [--size 9|13|19] \
+             [--rate <learning rate>] [--passes <n>] [--from <weights file>] [--augment]"
+        );
+        std::process::exit(2);
+    };
+    let size = arg_value("--size").unwrap_or_else(|| "19".to_string());
+    let result = match size.as_str() {
+        "9" => learn::<9>(&settings),
+        "13" => learn::<13>(&settings),
+        "19" => learn::<19>(&settings),
+        _ => {
+            eprintln!("Unsupported board size {size}, use 9, 13 or 19");
+            std::process::exit(2);
+        }
+    };
+    if let Err(err) = result {
+        eprintln!("{err}");
+        std::process::exit(1);
+    }
+}
+
+fn settings_from_args() -> Option<LearnSettings> {
+    Some(LearnSettings {
+        games: arg_value("--games").map(PathBuf::from)?,
+        out: arg_value("--out").map(PathBuf::from)?,
+        from: arg_value("--from").map(PathBuf::from),
+        learning_rate: arg_value("--rate")
+            .map_or(Some(DEFAULT_LEARNING_RATE), |rate| rate.parse().ok())?,
+        passes: arg_value("--passes").map_or(Some(1), |passes| passes.parse().ok())?,
+        augment: has_flag("--augment"),
+    })
+}
+
+/// The games of the `.sgf` files in the directory on the board size.
+fn load_games<const SIZE: usize>(
+    directory: &Path,
+) -> Result<Vec<GameTree<BadukClassical<SIZE>>>, Box<dyn std::error::Error>> {
+    let paths = sgf::sgf_files_in(directory)?;
+
+    let mut games = Vec::new();
+    for path in paths {
+        let contents = std::fs::read_to_string(&path)?;
+        for sgf_root in &sgf::parse(&contents)? {
+            match sgf::to_game_tree::<SIZE>(sgf_root) {
+                Ok(tree) => games.push(tree),
+                Err(SgfError::BoardSize { .. }) => continue,
+                Err(err) => return Err(format!("{}: {err}", path.display()).into()),
+            }
+        }
+    }
+    Ok(games)
+}
+
+fn learn<const SIZE: usize>(settings: &LearnSettings) -> Result<(), Box<dyn std::error::Error>> {
+    let games = load_games::<SIZE>(&settings.games)?;
+    let mut weights = match &settings.from {
+        Some(path) => PatternWeights::load(path)?,
+        None => PatternWeights::default(),
+    };
+    for pass in 1..=settings.passes {
+        for tree in &games {
+            if settings.augment {
+                learn_augmented(&mut weights, tree, settings.learning_rate);
+            } else {
+                weights.learn_game(tree, settings.learning_rate);
+            }
+        }
+        println!(
+            "Pass {pass}: {} games, {} pattern hashes",
+            games.len(),
+            weights.len()
+        );
+    }
+    weights.save(&settings.out)?;
+    println!("Weights written to {}", settings.out.display());
+    Ok(())
+}
+
+/// Learns from every move of the game's main line in each distinct orientation of the board.
+fn learn_augmented<const SIZE: usize>(
+    weights: &mut PatternWeights,
+    tree: &GameTree<BadukClassical<SIZE>>,
+    learning_rate: f32,
+) {
+    let mut game = &tree.root().game;
+    for (mv, node) in tree.main_line() {
+        for (sample, mv) in game.symmetric_samples(*mv) {
+            weights.learn(&sample, mv, learning_rate);
+        }
+        game = &node.game;
+    }
+}
@@ -0,0 +1,103 @@
+// Measures how the Monte Carlo search scales with the number of threads.
+//
+//     mcts_bench --size 9 --time 5 --max-threads 16
+//
+// Searches the empty board for the given number of seconds with 1, 2, 4, ... threads up to
+// `--max-threads`, one per core if it is not given, and prints the playouts per second of each run
+// against the single threaded one.
+
+use std::sync::Arc;
+
+use badukrs::bot::{default_registry, BotOptions, DynGameBot};
+use badukrs::cli::arg_value;
+use badukrs::game::{BadukClassical, StatelessGame};
+use badukrs::search::SearchContext;
+
+struct BenchSettings {
+    seconds: f32,
+    max_threads: usize,
+    seed: u64,
+}
+
+fn main() {
+    let Some(settings) = settings_from_args() else {
+        eprintln!(
+            "Usage: mcts_bench [--size 9|13|19] [--time <seconds>] [--max-threads <n>] \
+             [--seed <seed>]"
+        );
+        std::process::exit(2);
+    };
+    let size = arg_value("--size").unwrap_or_else(|| "9".to_string());
+    match size.as_str() {
+        "9" => run_bench::<9>(&settings),
+        "13" => run_bench::<13>(&settings),
+        "19" => run_bench::<19>(&settings),
+        _ => {
+            eprintln!("Unsupported board size {size}, use 9, 13 or 19");
+            std::process::exit(2);
+        }
+    }
+}
+
+fn settings_from_args() -> Option<BenchSettings> {
+    let cores = std::thread::available_parallelism().map_or(1, |threads| threads.get());
+    Some(BenchSettings {
+        seconds: arg_value("--time").map_or(Some(5.0), |time| time.parse().ok())?,
+        max_threads: arg_value("--max-threads")
+            .map_or(Some(cores), |threads| threads.parse().ok())?,
+        seed: arg_value("--seed").map_or(Some(1), |seed| seed.parse().ok())?,
+    })
+}
+
+/// 1, 2, 4, ... up to `max_threads`, which is always included.
+fn thread_counts(max_threads: usize) -> Vec<usize> {
+    let mut counts: Vec<usize> = std::iter::successors(Some(1), |threads| Some(threads * 2))
+        .take_while(|threads| *threads < max_threads)
+        .collect();
+    counts.push(max_threads.max(1));
+    counts
+}
+
+fn run_bench<const SIZE: usize>(settings: &BenchSettings) {
+    let registry = default_registry::<BadukClassical<SIZE>>();
+    let game = BadukClassical::<SIZE>::new();
+    println!(
+        "{SIZE}x{SIZE} empty board, {:.1}s per run, {} legal moves",
+        settings.seconds,
+        game.list_all_legal_moves().len()
+    );
+    println!("threads  playouts  playouts/s  speedup");
+
+    let mut single_thread_rate = None;
+    for threads in thread_counts(settings.max_threads) {
+        let options: BotOptions = [
+            ("threads", threads.to_string()),
+            ("time", settings.seconds.to_string()),
+            ("playouts", u32::MAX.to_string()),
+            ("ponder", false.to_string()),
+            ("seed", settings.seed.to_string()),
+        ]
+        .into_iter()
+        .map(|(name, value)| (name.to_string(), value))
+        .collect();
+        let bot: Arc<dyn DynGameBot<BadukClassical<SIZE>>> = match registry.create("mcts", &options)
+        {
+            Ok(bot) => bot,
+            Err(err) => {
+                eprintln!("{err}");
+                std::process::exit(1);
+            }
+        };
+
+        let _ = bot.select_move(&game, &SearchContext::new(SIZE));
+        let Some(report) = bot.search_report() else {
+            continue;
+        };
+        let rate = report.playouts as f32 / report.elapsed.as_secs_f32().max(f32::EPSILON);
+        let speedup = rate / *single_thread_rate.get_or_insert(rate);
+        println!(
+            "{threads:>7}  {:>8}  {rate:>10.0}  {speedup:>6.2}x",
+            report.playouts
+        );
+    }
+}
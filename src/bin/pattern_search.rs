@@ -0,0 +1,153 @@
+// Finds the games of a collection in which a shape was played.
+//
+//     pattern_search --games <directory> --pattern <pattern file> [--size 9|13|19] [--at <point>]
+//                    [--colours-swapped] [--limit 20]
+//
+// The pattern file draws the shape, see `Pattern` for the format. It is looked for anywhere on
+// the board in all eight orientations, or only at the matching places if `--at` gives the SGF
+// point, e.g. "aq", of its top left corner. `--colours-swapped` also finds it with black and
+// white swapped. Prints the games where it formed and the moves played next.
+
+use std::path::{Path, PathBuf};
+
+use badukrs::cli::{arg_value, has_flag};
+use badukrs::game::{move_label, BadukMove, Player};
+use badukrs::pattern_database::{Pattern, PatternDatabase, PatternQuery, PatternSearchError};
+use badukrs::sgf;
+
+const DEFAULT_LISTED_MATCHES: usize = 20;
+
+struct SearchSettings {
+    games: PathBuf,
+    pattern: PathBuf,
+    at: Option<String>,
+    colours_swapped: bool,
+    limit: usize,
+}
+
+fn main() {
+    let Some(settings) = settings_from_args() else {
+        eprintln!(
+            "Usage: pattern_search --games <directory> --pattern <pattern file> \
+             [--size 9|13|19] [--at <point>] [--colours-swapped] [--limit <n>]"
+        );
+        std::process::exit(2);
+    };
+    let size = arg_value("--size").unwrap_or_else(|| "19".to_string());
+    let result = match size.as_str() {
+        "9" => run_search::<9>(&settings),
+        "13" => run_search::<13>(&settings),
+        "19" => run_search::<19>(&settings),
+        _ => {
+            eprintln!("Unsupported board size {size}, use 9, 13 or 19");
+            std::process::exit(2);
+        }
+    };
+    if let Err(err) = result {
+        eprintln!("{err}");
+        std::process::exit(1);
+    }
+}
+
+fn settings_from_args() -> Option<SearchSettings> {
+    Some(SearchSettings {
+        games: arg_value("--games").map(PathBuf::from)?,
+        pattern: arg_value("--pattern").map(PathBuf::from)?,
+        at: arg_value("--at"),
+        colours_swapped: has_flag("--colours-swapped"),
+        limit: arg_value("--limit")
+            .map_or(Some(DEFAULT_LISTED_MATCHES), |limit| limit.parse().ok())?,
+    })
+}
+
+fn read_pattern(path: &Path) -> Result<Pattern, PatternSearchError> {
+    let text = std::fs::read_to_string(path).map_err(|source| PatternSearchError::Io {
+        path: path.to_path_buf(),
+        source,
+    })?;
+    Pattern::parse(&text)
+}
+
+fn run_search<const SIZE: usize>(
+    settings: &SearchSettings,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let pattern = read_pattern(&settings.pattern)?;
+    // `--at` names the top left point as drawn, the query wants the bottom left one
+    let anchor = match &settings.at {
+        Some(point) => {
+            let (r, c) = sgf::parse_point(point, SIZE)?
+                .ok_or_else(|| sgf::SgfError::InvalidPoint(point.clone()))?;
+            let r = (r + 1)
+                .checked_sub(pattern.height)
+                .ok_or(PatternSearchError::TooLarge)?;
+            Some((r, c))
+        }
+        None => None,
+    };
+    let query = PatternQuery {
+        anchor,
+        colours_swapped: settings.colours_swapped,
+    };
+
+    let database = PatternDatabase::<SIZE>::from_sgf_directory(&settings.games)?;
+    let results = database.search(&pattern, &query)?;
+    println!(
+        "{} matches in {} games, {} positions searched",
+        results.matches.len(),
+        database.games.len(),
+        database.positions()
+    );
+
+    for found in results.matches.iter().take(settings.limit) {
+        let game = &database.games[found.game];
+        let players = format!(
+            "{} vs {}",
+            game.info.black_player.as_deref().unwrap_or("?"),
+            game.info.white_player.as_deref().unwrap_or("?")
+        );
+        let result = game
+            .info
+            .result
+            .map_or_else(|| "no result".to_string(), |result| result.to_string());
+        let next = match found.next_move {
+            Some((player, mv)) => format!("next {} {}", colour(player), move_label(&mv)),
+            None => "game over".to_string(),
+        };
+        println!(
+            "{} ({players}, {result}): move {}, {next}",
+            game.path.display(),
+            found.move_number
+        );
+    }
+    if results.matches.len() > settings.limit {
+        println!("... and {} more", results.matches.len() - settings.limit);
+    }
+
+    if !results.continuations.is_empty() {
+        println!();
+        println!("Continuations, in the pattern as drawn:");
+    }
+    for continuation in &results.continuations {
+        let point = match continuation.point {
+            Some((r, c)) => move_label(&BadukMove::Play {
+                coordinates: (r, c),
+            }),
+            None => "Elsewhere".to_string(),
+        };
+        println!(
+            "{point} ({}): played {}, won {} ({:.0}%)",
+            colour(continuation.player),
+            continuation.count,
+            continuation.wins,
+            100.0 * continuation.wins as f32 / continuation.count as f32
+        );
+    }
+    Ok(())
+}
+
+fn colour(player: Player) -> &'static str {
+    match player {
+        Player::Black => "B",
+        Player::White => "W",
+    }
+}
@@ -0,0 +1,276 @@
+use indexmap::IndexMap;
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+use std::path::Path;
+use std::str::FromStr;
+use std::sync::Arc;
+use thiserror::Error;
+
+use crate::game::{MoveError, StatelessGame};
+use crate::local_patterns::{PatternWeightsError, PlayoutPatterns};
+use crate::mcts_bot::MctsBot;
+use crate::playout::PatternPlayout;
+use crate::random_bot::RandomBot;
+use crate::search::{SearchContext, SearchReport};
+use crate::solver_bot::SolverBot;
+
+/// Bot settings by name, as given in GTP `kgs-*` commands, on the command line or in the UI.
+pub type BotOptions = IndexMap<String, String>;
+
+#[derive(Error, Debug)]
+pub enum BotError {
+    #[error("Unknown Bot \"{0}\"")]
+    UnknownBot(String),
+    #[error("Unknown Bot Option \"{0}\"")]
+    UnknownOption(String),
+    #[error("Invalid Value \"{value}\" for Bot Option \"{name}\"")]
+    InvalidValue { name: String, value: String },
+    #[error("Invalid Bot Option \"{0}\", expected name=value")]
+    InvalidSyntax(String),
+    #[error("Could Not Load the Pattern Weights for Bot Option \"{name}\": {source}")]
+    Patterns {
+        name: String,
+        source: PatternWeightsError,
+    },
+}
+
+/// The settings of one kind of bot, which can also be read and changed by name.
+pub trait BotConfig: Default + Clone {
+    /// Every option name with its current value.
+    fn options(&self) -> Vec<(&'static str, String)>;
+    fn set_option(&mut self, name: &str, value: &str) -> Result<(), BotError>;
+
+    /// The default settings with the given options changed.
+    fn from_options(options: &BotOptions) -> Result<Self, BotError> {
+        let mut config = Self::default();
+        for (name, value) in options {
+            config.set_option(name, value)?;
+        }
+        Ok(config)
+    }
+}
+
+/// Parses an option value, naming the option if it does not parse.
+pub fn parse_option<T: FromStr>(name: &str, value: &str) -> Result<T, BotError> {
+    value.trim().parse().map_err(|_| BotError::InvalidValue {
+        name: name.to_string(),
+        value: value.to_string(),
+    })
+}
+
+/// Parses the `seed` option, an empty value means no seed.
+pub fn parse_seed(name: &str, value: &str) -> Result<Option<u64>, BotError> {
+    if value.trim().is_empty() {
+        return Ok(None);
+    }
+    parse_option(name, value).map(Some)
+}
+
+/// Loads the playout pattern weights named by the `patterns` option, an empty value means the
+/// default weights.
+pub fn parse_patterns(name: &str, value: &str) -> Result<PlayoutPatterns, BotError> {
+    if value.trim().is_empty() {
+        return Ok(PlayoutPatterns::default());
+    }
+    PlayoutPatterns::load(Path::new(value.trim())).map_err(|source| BotError::Patterns {
+        name: name.to_string(),
+        source,
+    })
+}
+
+/// The random numbers a bot uses for one move. With a seed they only depend on the seed and the
+/// move number, so the same seed and the same moves always lead to the same game.
+pub fn move_rng(seed: Option<u64>, move_number: usize) -> ChaCha8Rng {
+    match seed {
+        Some(seed) => {
+            let mut rng = ChaCha8Rng::seed_from_u64(seed);
+            rng.set_stream(move_number as u64);
+            rng
+        }
+        None => ChaCha8Rng::from_entropy(),
+    }
+}
+
+pub trait GameBot {
+    type Game: StatelessGame;
+    type Config: BotConfig;
+    fn from_config(config: Self::Config) -> Self;
+    fn config(&self) -> &Self::Config;
+    /// Picks a move for the player to move. The context tells the bot how long it may think and
+    /// when to stop early.
+    fn select_move(
+        &self,
+        game: &Self::Game,
+        context: &SearchContext,
+    ) -> Result<<Self::Game as StatelessGame>::Move, MoveError>;
+
+    fn supports_pondering(&self) -> bool {
+        false
+    }
+
+    /// Keeps searching while the opponent thinks about `game`, until the context's stop flag is
+    /// set. The next `select_move` can then build on that search.
+    fn ponder(&self, _game: &Self::Game, _context: &SearchContext) {}
+
+    /// What the last `select_move` did, for bots that search.
+    fn search_report(&self) -> Option<SearchReport> {
+        None
+    }
+}
+
+/// A bot of any kind, so that bots picked by name can be used the same way.
+pub trait DynGameBot<G: StatelessGame>: Send + Sync {
+    fn select_move(&self, game: &G, context: &SearchContext) -> Result<G::Move, MoveError>;
+    fn options(&self) -> Vec<(&'static str, String)>;
+    fn supports_pondering(&self) -> bool;
+    fn ponder(&self, game: &G, context: &SearchContext);
+    fn search_report(&self) -> Option<SearchReport>;
+}
+
+impl<B> DynGameBot<B::Game> for B
+where
+    B: GameBot + Send + Sync,
+{
+    fn select_move(
+        &self,
+        game: &B::Game,
+        context: &SearchContext,
+    ) -> Result<<B::Game as StatelessGame>::Move, MoveError> {
+        GameBot::select_move(self, game, context)
+    }
+
+    fn options(&self) -> Vec<(&'static str, String)> {
+        self.config().options()
+    }
+
+    fn supports_pondering(&self) -> bool {
+        GameBot::supports_pondering(self)
+    }
+
+    fn ponder(&self, game: &B::Game, context: &SearchContext) {
+        GameBot::ponder(self, game, context)
+    }
+
+    fn search_report(&self) -> Option<SearchReport> {
+        GameBot::search_report(self)
+    }
+}
+
+/// Creates a bot from its options.
+pub type BotFactory<G> = fn(&BotOptions) -> Result<Arc<dyn DynGameBot<G>>, BotError>;
+
+pub struct BotEntry<G: StatelessGame> {
+    pub name: &'static str,
+    pub description: &'static str,
+    create: BotFactory<G>,
+    default_options: fn() -> Vec<(&'static str, String)>,
+}
+
+impl<G: StatelessGame> BotEntry<G> {
+    pub fn create(&self, options: &BotOptions) -> Result<Arc<dyn DynGameBot<G>>, BotError> {
+        (self.create)(options)
+    }
+
+    pub fn default_options(&self) -> Vec<(&'static str, String)> {
+        (self.default_options)()
+    }
+}
+
+fn create_bot<B>(options: &BotOptions) -> Result<Arc<dyn DynGameBot<B::Game>>, BotError>
+where
+    B: GameBot + Send + Sync + 'static,
+{
+    let config = B::Config::from_options(options)?;
+    Ok(Arc::new(B::from_config(config)))
+}
+
+fn default_options<B: GameBot>() -> Vec<(&'static str, String)> {
+    B::Config::default().options()
+}
+
+/// The bots that can be picked by name, in the order they are listed.
+pub struct BotRegistry<G: StatelessGame> {
+    entries: Vec<BotEntry<G>>,
+}
+
+impl<G: StatelessGame> Default for BotRegistry<G> {
+    fn default() -> Self {
+        BotRegistry {
+            entries: Vec::new(),
+        }
+    }
+}
+
+impl<G: StatelessGame> BotRegistry<G> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn register<B>(&mut self, name: &'static str, description: &'static str)
+    where
+        B: GameBot<Game = G> + Send + Sync + 'static,
+    {
+        self.entries.push(BotEntry {
+            name,
+            description,
+            create: create_bot::<B>,
+            default_options: default_options::<B>,
+        });
+    }
+
+    pub fn entries(&self) -> &[BotEntry<G>] {
+        &self.entries
+    }
+
+    pub fn get(&self, name: &str) -> Option<&BotEntry<G>> {
+        self.entries.iter().find(|entry| entry.name == name)
+    }
+
+    pub fn create(
+        &self,
+        name: &str,
+        options: &BotOptions,
+    ) -> Result<Arc<dyn DynGameBot<G>>, BotError> {
+        self.get(name)
+            .ok_or_else(|| BotError::UnknownBot(name.to_string()))?
+            .create(options)
+    }
+
+    /// Creates a bot from a spec such as "mcts" or "mcts:playouts=2000,exploration=1.2".
+    pub fn create_from_spec(&self, spec: &str) -> Result<Arc<dyn DynGameBot<G>>, BotError> {
+        let (name, options) = parse_bot_spec(spec)?;
+        self.create(name, &options)
+    }
+}
+
+/// The bots that come with the crate.
+pub fn default_registry<G>() -> BotRegistry<G>
+where
+    G: PatternPlayout + PartialEq + Send + Sync + 'static,
+    G::Move: Send + Sync,
+{
+    let mut registry = BotRegistry::new();
+    registry.register::<RandomBot<G>>("random", "Plays random moves, without filling its own eyes");
+    registry.register::<MctsBot<G>>("mcts", "Monte Carlo tree search with random playouts");
+    registry.register::<SolverBot<G>>(
+        "solver",
+        "Alpha-beta search, plays perfectly on boards small enough to solve",
+    );
+    registry
+}
+
+/// Splits a spec such as "mcts:playouts=2000,exploration=1.2" into the bot name and its options.
+pub fn parse_bot_spec(spec: &str) -> Result<(&str, BotOptions), BotError> {
+    let (name, options_text) = spec.split_once(':').unwrap_or((spec, ""));
+    let mut options = BotOptions::new();
+    for option in options_text
+        .split(',')
+        .filter(|option| !option.trim().is_empty())
+    {
+        let (option_name, value) = option
+            .split_once('=')
+            .ok_or_else(|| BotError::InvalidSyntax(option.to_string()))?;
+        options.insert(option_name.trim().to_string(), value.trim().to_string());
+    }
+    Ok((name.trim(), options))
+}
@@ -0,0 +1,21 @@
+use std::path::PathBuf;
+
+/// The argument after `flag`, e.g. "2000" for `--playouts 2000`.
+pub fn arg_value(flag: &str) -> Option<String> {
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        if arg == flag {
+            return args.next();
+        }
+    }
+    None
+}
+
+pub fn path_from_args(flag: &str) -> Option<PathBuf> {
+    arg_value(flag).map(PathBuf::from)
+}
+
+/// Whether a flag without a value, such as `--list-bots`, was given.
+pub fn has_flag(flag: &str) -> bool {
+    std::env::args().skip(1).any(|arg| arg == flag)
+}
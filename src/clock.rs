@@ -0,0 +1,464 @@
+use std::time::Duration;
+use thiserror::Error;
+
+use crate::game::{GameInfo, GameResult, Player};
+
+/// How much thinking time each player gets.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum TimeControl {
+    /// The game is lost as soon as the main time runs out.
+    Absolute { main_time: Duration },
+    /// After the main time every move must be played within one period. Overrunning a period uses
+    /// it up and the game is lost when none are left.
+    ByoYomi {
+        main_time: Duration,
+        periods: u32,
+        period_time: Duration,
+    },
+    /// After the main time `stones` moves must be played within each period.
+    Canadian {
+        main_time: Duration,
+        stones: u32,
+        period_time: Duration,
+    },
+    /// Every move played adds the increment to the player's time.
+    Fischer {
+        main_time: Duration,
+        increment: Duration,
+    },
+}
+
+#[derive(Error, Debug)]
+#[error("Invalid Overtime \"{0}\"")]
+pub struct ParseTimeControlError(String);
+
+impl TimeControl {
+    /// Reads the time control from the game information, e.g. main time 600 with overtime
+    /// "5x30 byo-yomi". Games without a main time or overtime are untimed.
+    pub fn from_game_info(info: &GameInfo) -> Result<Option<TimeControl>, ParseTimeControlError> {
+        let overtime = info.overtime.as_deref().map(str::trim).unwrap_or_default();
+        let Some(main_time) = info.main_time else {
+            if overtime.is_empty() {
+                return Ok(None);
+            }
+            return TimeControl::parse(Duration::ZERO, overtime).map(Some);
+        };
+        TimeControl::parse(Duration::from_secs_f32(main_time.max(0.0)), overtime).map(Some)
+    }
+
+    /// Parses the overtime as written in game records: "5x30 byo-yomi", "25/600 canadian",
+    /// "10 fischer" or nothing for absolute time.
+    pub fn parse(
+        main_time: Duration,
+        overtime: &str,
+    ) -> Result<TimeControl, ParseTimeControlError> {
+        let error = || ParseTimeControlError(overtime.to_string());
+        let seconds = |text: &str| -> Result<Duration, ParseTimeControlError> {
+            let seconds: f32 = text.trim().parse().map_err(|_| error())?;
+            Duration::try_from_secs_f32(seconds).map_err(|_| error())
+        };
+
+        let overtime = overtime.trim();
+        if overtime.is_empty() {
+            return Ok(TimeControl::Absolute { main_time });
+        }
+        let (amount, system) = overtime.split_once(' ').ok_or_else(error)?;
+        let system = system.trim().to_ascii_lowercase();
+        let control = match system.as_str() {
+            "byo-yomi" | "byoyomi" | "japanese" => {
+                let (periods, period_time) = amount.split_once('x').ok_or_else(error)?;
+                TimeControl::ByoYomi {
+                    main_time,
+                    periods: periods.parse().map_err(|_| error())?,
+                    period_time: seconds(period_time)?,
+                }
+            }
+            "canadian" => {
+                let (stones, period_time) = amount.split_once('/').ok_or_else(error)?;
+                TimeControl::Canadian {
+                    main_time,
+                    stones: stones.parse().map_err(|_| error())?,
+                    period_time: seconds(period_time)?,
+                }
+            }
+            "fischer" => TimeControl::Fischer {
+                main_time,
+                increment: seconds(amount.trim_start_matches('+'))?,
+            },
+            _ => return Err(error()),
+        };
+        Ok(control)
+    }
+
+    pub fn main_time(&self) -> Duration {
+        match *self {
+            TimeControl::Absolute { main_time }
+            | TimeControl::ByoYomi { main_time, .. }
+            | TimeControl::Canadian { main_time, .. }
+            | TimeControl::Fischer { main_time, .. } => main_time,
+        }
+    }
+
+    /// The overtime as written in game records, the inverse of `parse`.
+    pub fn overtime(&self) -> Option<String> {
+        match *self {
+            TimeControl::Absolute { .. } => None,
+            TimeControl::ByoYomi {
+                periods,
+                period_time,
+                ..
+            } => Some(format!("{periods}x{} byo-yomi", period_time.as_secs_f32())),
+            TimeControl::Canadian {
+                stones,
+                period_time,
+                ..
+            } => Some(format!("{stones}/{} canadian", period_time.as_secs_f32())),
+            TimeControl::Fischer { increment, .. } => {
+                Some(format!("{} fischer", increment.as_secs_f32()))
+            }
+        }
+    }
+}
+
+/// The time one player has left.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct PlayerClock {
+    /// Main time left. Under Fischer this is all of the player's time.
+    pub main_time: Duration,
+    /// Byo-yomi periods left, the current one included.
+    pub periods: u32,
+    /// Time left in the current byo-yomi or Canadian period.
+    pub period_time: Duration,
+    /// Moves still to be played in the current Canadian period.
+    pub stones: u32,
+    pub flagged: bool,
+}
+
+impl PlayerClock {
+    pub fn new(control: &TimeControl) -> Self {
+        let (periods, period_time, stones) = match *control {
+            TimeControl::ByoYomi {
+                periods,
+                period_time,
+                ..
+            } => (periods, period_time, 0),
+            TimeControl::Canadian {
+                stones,
+                period_time,
+                ..
+            } => (0, period_time, stones),
+            TimeControl::Absolute { .. } | TimeControl::Fischer { .. } => (0, Duration::ZERO, 0),
+        };
+        PlayerClock {
+            main_time: control.main_time(),
+            periods,
+            period_time,
+            stones,
+            flagged: false,
+        }
+    }
+
+    /// Whether the main time is used up and the player is playing in byo-yomi or Canadian periods.
+    pub fn in_overtime(&self) -> bool {
+        self.main_time.is_zero()
+    }
+
+    /// The time the player can still spend on the current move without losing on time.
+    pub fn time_for_move(&self) -> Duration {
+        if self.flagged {
+            return Duration::ZERO;
+        }
+        self.main_time + self.period_time
+    }
+
+    /// Takes `elapsed` thinking time off the clock, first from the main time and then from the
+    /// overtime periods.
+    fn spend(&mut self, control: &TimeControl, elapsed: Duration) {
+        let from_main_time = elapsed.min(self.main_time);
+        self.main_time -= from_main_time;
+        let mut elapsed = elapsed - from_main_time;
+        if elapsed.is_zero() || self.flagged {
+            return;
+        }
+
+        match *control {
+            TimeControl::Absolute { .. } | TimeControl::Fischer { .. } => self.flagged = true,
+            TimeControl::ByoYomi { period_time, .. } => {
+                while elapsed >= self.period_time {
+                    elapsed -= self.period_time;
+                    self.periods = self.periods.saturating_sub(1);
+                    if self.periods == 0 {
+                        self.period_time = Duration::ZERO;
+                        self.flagged = true;
+                        return;
+                    }
+                    self.period_time = period_time;
+                }
+                self.period_time -= elapsed;
+            }
+            TimeControl::Canadian { .. } => {
+                if elapsed >= self.period_time {
+                    self.period_time = Duration::ZERO;
+                    self.flagged = true;
+                } else {
+                    self.period_time -= elapsed;
+                }
+            }
+        }
+    }
+
+    /// Ends the player's move: a byo-yomi period starts over, a Canadian period counts the move
+    /// and a Fischer clock gains its increment.
+    fn finish_move(&mut self, control: &TimeControl) {
+        if self.flagged {
+            return;
+        }
+        match *control {
+            TimeControl::Absolute { .. } => {}
+            TimeControl::ByoYomi { period_time, .. } => {
+                if self.in_overtime() {
+                    self.period_time = period_time;
+                }
+            }
+            TimeControl::Canadian {
+                stones,
+                period_time,
+                ..
+            } => {
+                if self.in_overtime() {
+                    self.stones = self.stones.saturating_sub(1);
+                    if self.stones == 0 {
+                        self.stones = stones;
+                        self.period_time = period_time;
+                    }
+                }
+            }
+            TimeControl::Fischer { increment, .. } => self.main_time += increment,
+        }
+    }
+}
+
+/// Where the clock reads the time from, so that it can be driven by hand in tests.
+pub trait TimeSource {
+    /// The time passed since some fixed starting point.
+    fn now(&self) -> Duration;
+}
+
+/// A time source that only moves when it is advanced, e.g. by the frame time of the app.
+#[derive(Clone, Copy, Default, Debug)]
+pub struct ManualTime(Duration);
+
+impl ManualTime {
+    pub fn advance(&mut self, elapsed: Duration) {
+        self.0 += elapsed;
+    }
+}
+
+impl TimeSource for ManualTime {
+    fn now(&self) -> Duration {
+        self.0
+    }
+}
+
+/// A chess clock for both players. At most one of the two clocks runs at a time.
+pub struct GameClock<T: TimeSource> {
+    pub control: TimeControl,
+    black: PlayerClock,
+    white: PlayerClock,
+    // Whose clock is running and when it was started
+    running: Option<(Player, Duration)>,
+    source: T,
+}
+
+impl<T: TimeSource> GameClock<T> {
+    pub fn new(control: TimeControl, source: T) -> Self {
+        GameClock {
+            control,
+            black: PlayerClock::new(&control),
+            white: PlayerClock::new(&control),
+            running: None,
+            source,
+        }
+    }
+
+    pub fn source(&self) -> &T {
+        &self.source
+    }
+
+    pub fn source_mut(&mut self) -> &mut T {
+        &mut self.source
+    }
+
+    fn clock_mut(&mut self, player: Player) -> &mut PlayerClock {
+        match player {
+            Player::Black => &mut self.black,
+            Player::White => &mut self.white,
+        }
+    }
+
+    /// Whose clock is running, if any.
+    pub fn running(&self) -> Option<Player> {
+        self.running.map(|(player, _)| player)
+    }
+
+    /// The time `player` has left right now, counting the time spent on a move in progress.
+    pub fn remaining(&self, player: Player) -> PlayerClock {
+        let mut clock = match player {
+            Player::Black => self.black,
+            Player::White => self.white,
+        };
+        if let Some((running, started)) = self.running {
+            if running == player {
+                clock.spend(&self.control, self.source.now().saturating_sub(started));
+            }
+        }
+        clock
+    }
+
+    /// The player who ran out of time, whose clock stays stopped.
+    pub fn flagged(&self) -> Option<Player> {
+        [Player::Black, Player::White]
+            .into_iter()
+            .find(|player| self.remaining(*player).flagged)
+    }
+
+    /// A loss on time for the player who ran out of it.
+    pub fn result(&self) -> Option<GameResult> {
+        self.flagged().map(|loser| GameResult::Time {
+            winner: loser.opponent(),
+        })
+    }
+
+    /// Starts `player`'s clock, stopping the other one without ending its move.
+    pub fn start(&mut self, player: Player) {
+        if self.flagged().is_some() {
+            return;
+        }
+        self.pause();
+        self.running = Some((player, self.source.now()));
+    }
+
+    /// Stops the running clock, keeping the time spent so far on the move.
+    pub fn pause(&mut self) {
+        if let Some((player, _)) = self.running {
+            let clock = self.remaining(player);
+            *self.clock_mut(player) = clock;
+            self.running = None;
+        }
+    }
+
+    /// Ends `player`'s move and starts the opponent's clock. Nothing starts once a player ran out
+    /// of time.
+    pub fn end_move(&mut self, player: Player) {
+        let finishes_move = self.running() == Some(player);
+        self.pause();
+        if self.flagged().is_some() {
+            return;
+        }
+        if finishes_move {
+            let control = self.control;
+            self.clock_mut(player).finish_move(&control);
+        }
+        self.start(player.opponent());
+    }
+
+    /// Sets both clocks back to the full time and stops them.
+    pub fn reset(&mut self) {
+        self.black = PlayerClock::new(&self.control);
+        self.white = PlayerClock::new(&self.control);
+        self.running = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn seconds(seconds: u64) -> Duration {
+        Duration::from_secs(seconds)
+    }
+
+    // Starts Black's clock, lets `elapsed` pass and ends Black's move
+    fn black_moves(clock: &mut GameClock<ManualTime>, elapsed: Duration) {
+        clock.start(Player::Black);
+        clock.source_mut().advance(elapsed);
+        clock.end_move(Player::Black);
+    }
+
+    #[test]
+    fn byo_yomi_uses_up_overrun_periods() {
+        let control = TimeControl::ByoYomi {
+            main_time: seconds(10),
+            periods: 3,
+            period_time: seconds(30),
+        };
+        let mut clock = GameClock::new(control, ManualTime::default());
+
+        black_moves(&mut clock, seconds(25));
+        let black = clock.remaining(Player::Black);
+        assert!(black.in_overtime());
+        assert_eq!((black.periods, black.period_time), (3, seconds(30)));
+
+        // 75 seconds overruns two periods and starts the move over in the last one
+        black_moves(&mut clock, seconds(75));
+        let black = clock.remaining(Player::Black);
+        assert_eq!((black.periods, black.period_time), (1, seconds(30)));
+        assert!(!black.flagged);
+    }
+
+    #[test]
+    fn canadian_period_starts_over_after_its_stones() {
+        let control = TimeControl::Canadian {
+            main_time: Duration::ZERO,
+            stones: 2,
+            period_time: seconds(60),
+        };
+        let mut clock = GameClock::new(control, ManualTime::default());
+
+        black_moves(&mut clock, seconds(20));
+        let black = clock.remaining(Player::Black);
+        assert_eq!((black.stones, black.period_time), (1, seconds(40)));
+
+        black_moves(&mut clock, seconds(30));
+        let black = clock.remaining(Player::Black);
+        assert_eq!((black.stones, black.period_time), (2, seconds(60)));
+    }
+
+    #[test]
+    fn fischer_adds_the_increment_after_each_move() {
+        let control = TimeControl::Fischer {
+            main_time: seconds(60),
+            increment: seconds(10),
+        };
+        let mut clock = GameClock::new(control, ManualTime::default());
+
+        black_moves(&mut clock, seconds(15));
+        assert_eq!(clock.remaining(Player::Black).main_time, seconds(55));
+        assert_eq!(clock.remaining(Player::White).main_time, seconds(60));
+        assert_eq!(clock.running(), Some(Player::White));
+    }
+
+    #[test]
+    fn running_out_of_time_loses_the_game() {
+        let control = TimeControl::Absolute {
+            main_time: seconds(30),
+        };
+        let mut clock = GameClock::new(control, ManualTime::default());
+
+        clock.start(Player::White);
+        clock.source_mut().advance(seconds(29));
+        assert_eq!(clock.flagged(), None);
+        clock.source_mut().advance(seconds(2));
+        assert_eq!(clock.flagged(), Some(Player::White));
+        assert_eq!(
+            clock.result(),
+            Some(GameResult::Time {
+                winner: Player::Black
+            })
+        );
+
+        // The flagged clock stays stopped and nothing else starts
+        clock.end_move(Player::White);
+        assert_eq!(clock.running(), None);
+    }
+}
@@ -0,0 +1,439 @@
+use indexmap::IndexMap;
+use std::fmt::Write as _;
+
+use crate::game::{move_label, BadukClassical, BadukMove, GameResult, GameTree, Player};
+
+/// How a player did over the collection.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct PlayerStats {
+    pub games: u32,
+    pub as_black: u32,
+    pub wins: u32,
+    pub losses: u32,
+    /// Draws and games without a winner.
+    pub other: u32,
+}
+
+/// How many games ended which way.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ResultCounts {
+    pub black_wins: u32,
+    pub white_wins: u32,
+    pub counted: u32,
+    pub resigned: u32,
+    pub time: u32,
+    pub forfeit: u32,
+    pub draws: u32,
+    /// Void games and games without a recorded result.
+    pub unfinished: u32,
+}
+
+/// Summaries of a game collection, built up one game at a time.
+///
+/// First moves and openings are counted in the orientation that makes the position after them
+/// canonical, so the same opening played in another corner counts as the same one.
+#[derive(Clone, Debug, Default)]
+pub struct CollectionStats {
+    pub games: u32,
+    pub total_moves: u64,
+    pub results: ResultCounts,
+    pub players: IndexMap<String, PlayerStats>,
+    pub board_sizes: IndexMap<usize, u32>,
+    pub komi: IndexMap<String, u32>,
+    pub rules: IndexMap<String, u32>,
+    /// The first move of each game, e.g. "Q16".
+    pub first_moves: IndexMap<String, u32>,
+    /// The first moves of each game that went on long enough, e.g. "Q16 D4 Q3 D16".
+    pub openings: IndexMap<String, u32>,
+}
+
+impl CollectionStats {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The mean number of moves in the main lines, passes included.
+    pub fn average_length(&self) -> f32 {
+        if self.games == 0 {
+            0.0
+        } else {
+            self.total_moves as f32 / self.games as f32
+        }
+    }
+
+    /// Counts the game's main line and header. Openings are its first `opening_moves` moves.
+    pub fn add_game<const SIZE: usize>(
+        &mut self,
+        tree: &GameTree<BadukClassical<SIZE>>,
+        opening_moves: usize,
+    ) {
+        let info = &tree.info;
+        self.games += 1;
+        *self.board_sizes.entry(SIZE).or_insert(0) += 1;
+        *self.komi.entry(info.komi.to_string()).or_insert(0) += 1;
+        let rules = info.rules.clone().unwrap_or_else(|| "Unknown".to_string());
+        *self.rules.entry(rules).or_insert(0) += 1;
+
+        let winner = info.result.and_then(|result| result.winner());
+        self.count_result(info.result);
+        for (player, name) in [
+            (Player::Black, &info.black_player),
+            (Player::White, &info.white_player),
+        ] {
+            let Some(name) = name
+                .as_deref()
+                .map(str::trim)
+                .filter(|name| !name.is_empty())
+            else {
+                continue;
+            };
+            let stats = self.players.entry(name.to_string()).or_default();
+            stats.games += 1;
+            stats.as_black += u32::from(player == Player::Black);
+            match winner {
+                Some(winner) if winner == player => stats.wins += 1,
+                Some(_) => stats.losses += 1,
+                None => stats.other += 1,
+            }
+        }
+
+        let moves: Vec<BadukMove> = tree.main_line().map(|(mv, _)| *mv).collect();
+        self.total_moves += moves.len() as u64;
+        if let Some(first_move) = canonical_line(tree, 1) {
+            *self.first_moves.entry(first_move).or_insert(0) += 1;
+        }
+        if opening_moves > 0 && moves.len() >= opening_moves {
+            if let Some(opening) = canonical_line(tree, opening_moves) {
+                *self.openings.entry(opening).or_insert(0) += 1;
+            }
+        }
+    }
+
+    fn count_result(&mut self, result: Option<GameResult>) {
+        let results = &mut self.results;
+        match result.and_then(|result| result.winner()) {
+            Some(Player::Black) => results.black_wins += 1,
+            Some(Player::White) => results.white_wins += 1,
+            None => {}
+        }
+        match result {
+            Some(GameResult::Score { .. }) => results.counted += 1,
+            Some(GameResult::Resignation { .. }) => results.resigned += 1,
+            Some(GameResult::Time { .. }) => results.time += 1,
+            Some(GameResult::Forfeit { .. }) => results.forfeit += 1,
+            Some(GameResult::Draw) => results.draws += 1,
+            Some(GameResult::Void) | None => results.unfinished += 1,
+        }
+    }
+
+    /// The statistics as plain text tables, listing the `top` most common first moves and
+    /// openings.
+    pub fn to_table(&self, top: usize) -> String {
+        let mut text = String::new();
+        let results = &self.results;
+        let _ = writeln!(text, "Games: {}", self.games);
+        let _ = writeln!(text, "Average length: {:.1} moves", self.average_length());
+        let _ = writeln!(
+            text,
+            "Black wins: {} ({}), White wins: {} ({})",
+            results.black_wins,
+            percent(results.black_wins, self.games),
+            results.white_wins,
+            percent(results.white_wins, self.games)
+        );
+        let decided = results.counted + results.resigned;
+        let _ = writeln!(
+            text,
+            "Resigned: {} ({}), counted: {} ({}), time: {}, forfeit: {}, draws: {}, \
+             unfinished: {}",
+            results.resigned,
+            percent(results.resigned, decided),
+            results.counted,
+            percent(results.counted, decided),
+            results.time,
+            results.forfeit,
+            results.draws,
+            results.unfinished
+        );
+
+        let sizes: Vec<(String, u32)> = self
+            .board_sizes
+            .iter()
+            .map(|(size, count)| (format!("{size}x{size}"), *count))
+            .collect();
+        write_counts(&mut text, "Board sizes", &sizes, usize::MAX);
+        write_counts(&mut text, "Komi", &sorted_counts(&self.komi), usize::MAX);
+        write_counts(&mut text, "Rules", &sorted_counts(&self.rules), usize::MAX);
+        write_counts(
+            &mut text,
+            "First moves",
+            &sorted_counts(&self.first_moves),
+            top,
+        );
+        write_counts(&mut text, "Openings", &sorted_counts(&self.openings), top);
+
+        let _ = writeln!(text);
+        let _ = writeln!(text, "Players:");
+        let width = self
+            .players
+            .keys()
+            .map(|name| name.chars().count())
+            .max()
+            .unwrap_or(0)
+            .max("Name".len());
+        let _ = writeln!(
+            text,
+            "  {:width$}  {:>6}  {:>6}  {:>6}  {:>6}  {:>6}  {:>6}",
+            "Name", "Games", "Black", "Wins", "Losses", "Other", "Win %"
+        );
+        for (name, stats) in self.sorted_players() {
+            let _ = writeln!(
+                text,
+                "  {name:width$}  {:>6}  {:>6}  {:>6}  {:>6}  {:>6}  {:>6}",
+                stats.games,
+                stats.as_black,
+                stats.wins,
+                stats.losses,
+                stats.other,
+                percent(stats.wins, stats.games)
+            );
+        }
+        text
+    }
+
+    /// The statistics as a JSON object, listing the `top` most common first moves and openings.
+    pub fn to_json(&self, top: usize) -> String {
+        let results = &self.results;
+        let counts = |counts: &[(String, u32)], limit: usize| {
+            let entries: Vec<String> = counts
+                .iter()
+                .take(limit)
+                .map(|(key, count)| format!("{}: {count}", json_string(key)))
+                .collect();
+            format!("{{{}}}", entries.join(", "))
+        };
+        let sizes: Vec<(String, u32)> = self
+            .board_sizes
+            .iter()
+            .map(|(size, count)| (size.to_string(), *count))
+            .collect();
+        let players: Vec<String> = self
+            .sorted_players()
+            .into_iter()
+            .map(|(name, stats)| {
+                format!(
+                    "    {}: {{\"games\": {}, \"as_black\": {}, \"wins\": {}, \"losses\": {}, \
+                     \"other\": {}}}",
+                    json_string(name),
+                    stats.games,
+                    stats.as_black,
+                    stats.wins,
+                    stats.losses,
+                    stats.other
+                )
+            })
+            .collect();
+
+        let fields = [
+            format!("\"games\": {}", self.games),
+            format!("\"average_length\": {:.2}", self.average_length()),
+            format!(
+                "\"results\": {{\"black_wins\": {}, \"white_wins\": {}, \"counted\": {}, \
+                 \"resigned\": {}, \"time\": {}, \"forfeit\": {}, \"draws\": {}, \
+                 \"unfinished\": {}}}",
+                results.black_wins,
+                results.white_wins,
+                results.counted,
+                results.resigned,
+                results.time,
+                results.forfeit,
+                results.draws,
+                results.unfinished
+            ),
+            format!("\"board_sizes\": {}", counts(&sizes, usize::MAX)),
+            format!(
+                "\"komi\": {}",
+                counts(&sorted_counts(&self.komi), usize::MAX)
+            ),
+            format!(
+                "\"rules\": {}",
+                counts(&sorted_counts(&self.rules), usize::MAX)
+            ),
+            format!(
+                "\"first_moves\": {}",
+                counts(&sorted_counts(&self.first_moves), top)
+            ),
+            format!(
+                "\"openings\": {}",
+                counts(&sorted_counts(&self.openings), top)
+            ),
+            format!("\"players\": {{\n{}\n  }}", players.join(",\n")),
+        ];
+        let fields: Vec<String> = fields.iter().map(|field| format!("  {field}")).collect();
+        format!("{{\n{}\n}}\n", fields.join(",\n"))
+    }
+
+    /// Players with the most games first, then by name.
+    fn sorted_players(&self) -> Vec<(&String, &PlayerStats)> {
+        let mut players: Vec<(&String, &PlayerStats)> = self.players.iter().collect();
+        players.sort_by(|a, b| b.1.games.cmp(&a.1.games).then(a.0.cmp(b.0)));
+        players
+    }
+}
+
+/// The first moves of the main line turned so that the position after them is in its canonical
+/// orientation, as labels. `None` if the game is shorter or passes or resigns before.
+fn canonical_line<const SIZE: usize>(
+    tree: &GameTree<BadukClassical<SIZE>>,
+    moves: usize,
+) -> Option<String> {
+    let line: Vec<(&BadukMove, _)> = tree.main_line().take(moves).collect();
+    if line.len() < moves
+        || line
+            .iter()
+            .any(|(mv, _)| !matches!(mv, BadukMove::Play { .. }))
+    {
+        return None;
+    }
+    let (_, last) = line.last()?;
+    let (_, symmetry) = last.game.canonical_hash();
+    let labels: Vec<String> = line
+        .iter()
+        .map(|(mv, _)| move_label(&mv.transformed(symmetry, SIZE)))
+        .collect();
+    Some(labels.join(" "))
+}
+
+fn sorted_counts<K: ToString>(counts: &IndexMap<K, u32>) -> Vec<(String, u32)> {
+    let mut sorted: Vec<(String, u32)> = counts
+        .iter()
+        .map(|(key, count)| (key.to_string(), *count))
+        .collect();
+    sorted.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+    sorted
+}
+
+fn write_counts(text: &mut String, title: &str, counts: &[(String, u32)], limit: usize) {
+    let _ = writeln!(text);
+    let _ = writeln!(text, "{title}:");
+    if counts.is_empty() {
+        let _ = writeln!(text, "  None");
+    }
+    for (key, count) in counts.iter().take(limit) {
+        let _ = writeln!(text, "  {key}: {count}");
+    }
+    if counts.len() > limit {
+        let _ = writeln!(text, "  ... and {} more", counts.len() - limit);
+    }
+}
+
+fn percent(count: u32, total: u32) -> String {
+    if total == 0 {
+        "-".to_string()
+    } else {
+        format!("{:.1}%", 100.0 * count as f32 / total as f32)
+    }
+}
+
+fn json_string(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len() + 2);
+    escaped.push('"');
+    for character in text.chars() {
+        match character {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            character if character.is_control() => {
+                let _ = write!(escaped, "\\u{:04x}", character as u32);
+            }
+            character => escaped.push(character),
+        }
+    }
+    escaped.push('"');
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sgf;
+
+    fn game(record: &str) -> GameTree<BadukClassical<9>> {
+        sgf::to_game_tree::<9>(&sgf::parse(record).unwrap()[0]).unwrap()
+    }
+
+    // The first two games open with the same two moves in opposite corners
+    fn stats() -> CollectionStats {
+        let mut stats = CollectionStats::new();
+        for record in [
+            "(;SZ[9]PB[Alice]PW[Bob]RE[B+R]KM[6.5]RU[Japanese];B[cc];W[gg];B[cg];W[gc])",
+            "(;SZ[9]PB[Bob]PW[Alice]RE[W+3.5]KM[6.5]RU[Japanese];B[gg];W[cc];B[gc])",
+            "(;SZ[9]PB[Alice]PW[Carol \"C\"]RE[Void]KM[7];B[ee];W[cc])",
+        ] {
+            stats.add_game(&game(record), 2);
+        }
+        stats
+    }
+
+    #[test]
+    fn games_results_and_players_are_counted() {
+        let stats = stats();
+        assert_eq!(stats.games, 3);
+        assert_eq!(stats.total_moves, 9);
+        assert_eq!(stats.average_length(), 3.0);
+        assert_eq!(
+            stats.results,
+            ResultCounts {
+                black_wins: 1,
+                white_wins: 1,
+                counted: 1,
+                resigned: 1,
+                unfinished: 1,
+                ..ResultCounts::default()
+            }
+        );
+        assert_eq!(
+            stats.players["Alice"],
+            PlayerStats {
+                games: 3,
+                as_black: 2,
+                wins: 2,
+                losses: 0,
+                other: 1,
+            }
+        );
+        assert_eq!(stats.players["Bob"].losses, 2);
+        assert_eq!(stats.komi["6.5"], 2);
+        assert_eq!(stats.rules["Unknown"], 1);
+    }
+
+    #[test]
+    fn openings_in_other_corners_count_as_the_same() {
+        let stats = stats();
+        assert_eq!(stats.first_moves.len(), 2);
+        assert_eq!(sorted_counts(&stats.first_moves)[0].1, 2);
+        assert_eq!(stats.openings.len(), 2);
+        assert_eq!(sorted_counts(&stats.openings)[0].1, 2);
+    }
+
+    #[test]
+    fn tables_and_json_list_the_top_entries() {
+        let stats = stats();
+        let table = stats.to_table(1);
+        assert!(table.contains("Games: 3\n"));
+        assert!(table.contains("Black wins: 1 (33.3%), White wins: 1 (33.3%)"));
+        assert!(table.contains("  ... and 1 more\n"));
+
+        let json = stats.to_json(1);
+        assert!(json.starts_with("{\n  \"games\": 3,\n"));
+        assert!(json.contains("\"Alice\": {\"games\": 3, \"as_black\": 2, \"wins\": 2"));
+        assert!(json.contains("\"Carol \\\"C\\\"\": {\"games\": 1"));
+        let first_moves = json
+            .lines()
+            .find(|line| line.contains("\"first_moves\""))
+            .unwrap();
+        assert_eq!(first_moves.matches(':').count(), 2, "{first_moves}");
+    }
+}
@@ -0,0 +1,477 @@
+use rand_chacha::ChaCha8Rng;
+use std::fmt;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+use thiserror::Error;
+
+use crate::game::StatelessGame;
+use crate::local_patterns::PatternWeights;
+use crate::playout::{random_playout, PatternPlayout, PlayoutPolicy};
+
+#[derive(Error, Debug, Clone, PartialEq)]
+pub enum EvaluationError {
+    #[error("Evaluation Queue Closed")]
+    QueueClosed,
+    #[error("Evaluator Returned {returned} Results for {positions} Positions")]
+    WrongResultCount { positions: usize, returned: usize },
+    #[error("Inference Failed: {0}")]
+    Inference(String),
+}
+
+/// What an evaluator thinks of one position.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Evaluation<M> {
+    /// Prior probabilities for the moves worth searching.
+    pub policy: Vec<(M, f32)>,
+    /// The chance that the player to move wins, from 0 to 1.
+    pub value: f32,
+}
+
+/// Evaluates positions several at a time, such as a neural network running one inference per
+/// batch.
+pub trait Evaluator<G: StatelessGame>: Send + 'static {
+    /// One evaluation per position, in the same order.
+    fn evaluate_batch(
+        &mut self,
+        positions: &[G],
+    ) -> Result<Vec<Evaluation<G::Move>>, EvaluationError>;
+}
+
+/// Judges positions by a few random playouts from each, a whole batch at a time on the
+/// evaluation thread, for searching with an evaluator on machines without a network. The policy
+/// is how often the playouts started with each move and the value how many of them the player to
+/// move won.
+pub struct PlayoutEvaluator {
+    /// The playouts run from every position.
+    pub playouts: usize,
+    pub policy: PlayoutPolicy,
+    pub weights: Arc<PatternWeights>,
+    pub komi: f32,
+    /// The most moves of one playout.
+    pub max_moves: usize,
+    rng: ChaCha8Rng,
+}
+
+impl PlayoutEvaluator {
+    pub const DEFAULT_PLAYOUTS: usize = 8;
+
+    pub fn new(
+        policy: PlayoutPolicy,
+        weights: Arc<PatternWeights>,
+        komi: f32,
+        max_moves: usize,
+        rng: ChaCha8Rng,
+    ) -> Self {
+        PlayoutEvaluator {
+            playouts: Self::DEFAULT_PLAYOUTS,
+            policy,
+            weights,
+            komi,
+            max_moves,
+            rng,
+        }
+    }
+
+    fn evaluate<G: PatternPlayout>(&mut self, game: &G) -> Evaluation<G::Move> {
+        let mut first_moves: Vec<(G::Move, f32)> = Vec::new();
+        let mut played = 0;
+        // Twice the wins, so that a drawn playout counts as half a win
+        let mut half_wins = 0;
+        for _ in 0..self.playouts {
+            let Some(mv) = self.policy.choose_move(game, &self.weights, &mut self.rng) else {
+                break;
+            };
+            let Ok(next) = game.generate_next_board(&mv) else {
+                continue;
+            };
+            match first_moves.iter_mut().find(|(known, _)| *known == mv) {
+                Some((_, count)) => *count += 1.0,
+                None => first_moves.push((mv, 1.0)),
+            }
+            let winner = random_playout(
+                next,
+                self.policy,
+                &self.weights,
+                self.komi,
+                self.max_moves,
+                &mut self.rng,
+            );
+            played += 1;
+            half_wins += match winner {
+                Some(player) if player == game.turn() => 2,
+                Some(_) => 0,
+                None => 1,
+            };
+        }
+
+        if played == 0 {
+            return Evaluation {
+                policy: Vec::new(),
+                value: 0.5,
+            };
+        }
+        for (_, count) in &mut first_moves {
+            *count /= played as f32;
+        }
+        Evaluation {
+            policy: first_moves,
+            value: half_wins as f32 / (2 * played) as f32,
+        }
+    }
+}
+
+impl<G: PatternPlayout> Evaluator<G> for PlayoutEvaluator {
+    fn evaluate_batch(
+        &mut self,
+        positions: &[G],
+    ) -> Result<Vec<Evaluation<G::Move>>, EvaluationError> {
+        Ok(positions.iter().map(|game| self.evaluate(game)).collect())
+    }
+}
+
+/// The evaluator a bot builds from its options when none is given in code.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum EvaluatorKind {
+    /// No evaluator, the search threads play the leaves out themselves.
+    #[default]
+    None,
+    /// A `PlayoutEvaluator`.
+    Playouts,
+}
+
+impl fmt::Display for EvaluatorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EvaluatorKind::None => write!(f, "none"),
+            EvaluatorKind::Playouts => write!(f, "playouts"),
+        }
+    }
+}
+
+#[derive(Error, Debug)]
+#[error("Unknown Evaluator \"{0}\"")]
+pub struct ParseEvaluatorError(String);
+
+impl FromStr for EvaluatorKind {
+    type Err = ParseEvaluatorError;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        match text.trim() {
+            "none" | "" => Ok(EvaluatorKind::None),
+            "playouts" => Ok(EvaluatorKind::Playouts),
+            _ => Err(ParseEvaluatorError(text.to_string())),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct BatchConfig {
+    /// The most positions evaluated together.
+    pub batch_size: usize,
+    /// How long the first position of a batch waits for more to arrive before the batch is run
+    /// anyway.
+    pub max_wait: Duration,
+}
+
+impl Default for BatchConfig {
+    fn default() -> Self {
+        BatchConfig {
+            batch_size: 16,
+            max_wait: Duration::from_millis(2),
+        }
+    }
+}
+
+/// How full the batches were, for tuning the batch size and wait.
+#[derive(Clone, Copy, Default, Debug, PartialEq)]
+pub struct BatchStats {
+    pub positions: u64,
+    pub batches: u64,
+}
+
+impl BatchStats {
+    pub fn average_batch_size(&self) -> f32 {
+        self.positions as f32 / self.batches.max(1) as f32
+    }
+}
+
+type EvaluationResult<M> = Result<Evaluation<M>, EvaluationError>;
+
+struct EvaluationRequest<G: StatelessGame> {
+    game: G,
+    reply: Sender<EvaluationResult<G::Move>>,
+}
+
+#[derive(Default)]
+struct BatchCounters {
+    positions: AtomicU64,
+    batches: AtomicU64,
+}
+
+/// Collects the positions that search threads want evaluated into batches, runs the evaluator
+/// on one thread of its own and hands every search thread back its result.
+pub struct EvaluationQueue<G: StatelessGame> {
+    // Taken when the queue is dropped, which ends the evaluator thread
+    requests: Mutex<Option<Sender<EvaluationRequest<G>>>>,
+    worker: Option<JoinHandle<()>>,
+    counters: Arc<BatchCounters>,
+}
+
+impl<G> EvaluationQueue<G>
+where
+    G: StatelessGame + Send + 'static,
+    G::Move: Send,
+{
+    pub fn new(evaluator: impl Evaluator<G>, config: BatchConfig) -> Self {
+        let (requests, receiver) = mpsc::channel();
+        let counters = Arc::new(BatchCounters::default());
+        let worker_counters = counters.clone();
+        let worker = std::thread::Builder::new()
+            .name("evaluation".to_string())
+            .spawn(move || run_batches(evaluator, &config, &receiver, &worker_counters))
+            .expect("Could not start the evaluation thread");
+        EvaluationQueue {
+            requests: Mutex::new(Some(requests)),
+            worker: Some(worker),
+            counters,
+        }
+    }
+
+    /// Queues the position and waits until the batch it ends up in has been evaluated.
+    pub fn evaluate(&self, game: &G) -> EvaluationResult<G::Move> {
+        let (reply, result) = mpsc::channel();
+        let request = EvaluationRequest {
+            game: game.clone(),
+            reply,
+        };
+        let sent = match &*self.requests.lock().unwrap_or_else(|err| err.into_inner()) {
+            Some(requests) => requests.send(request).is_ok(),
+            None => false,
+        };
+        if !sent {
+            return Err(EvaluationError::QueueClosed);
+        }
+        result.recv().unwrap_or(Err(EvaluationError::QueueClosed))
+    }
+
+    pub fn stats(&self) -> BatchStats {
+        BatchStats {
+            positions: self.counters.positions.load(Ordering::Relaxed),
+            batches: self.counters.batches.load(Ordering::Relaxed),
+        }
+    }
+}
+
+impl<G: StatelessGame> Drop for EvaluationQueue<G> {
+    fn drop(&mut self) {
+        self.requests
+            .lock()
+            .unwrap_or_else(|err| err.into_inner())
+            .take();
+        if let Some(worker) = self.worker.take() {
+            let _ = worker.join();
+        }
+    }
+}
+
+/// Runs on the evaluator thread until every sender is gone.
+fn run_batches<G: StatelessGame>(
+    mut evaluator: impl Evaluator<G>,
+    config: &BatchConfig,
+    receiver: &Receiver<EvaluationRequest<G>>,
+    counters: &BatchCounters,
+) {
+    let batch_size = config.batch_size.max(1);
+    while let Ok(first) = receiver.recv() {
+        let deadline = Instant::now() + config.max_wait;
+        let mut batch = vec![first];
+        while batch.len() < batch_size {
+            match receiver.recv_timeout(deadline.saturating_duration_since(Instant::now())) {
+                Ok(request) => batch.push(request),
+                Err(RecvTimeoutError::Timeout | RecvTimeoutError::Disconnected) => break,
+            }
+        }
+
+        let (games, replies): (Vec<G>, Vec<_>) = batch
+            .into_iter()
+            .map(|request| (request.game, request.reply))
+            .unzip();
+        counters
+            .positions
+            .fetch_add(games.len() as u64, Ordering::Relaxed);
+        counters.batches.fetch_add(1, Ordering::Relaxed);
+
+        match evaluator.evaluate_batch(&games) {
+            Ok(evaluations) if evaluations.len() == games.len() => {
+                for (reply, evaluation) in replies.into_iter().zip(evaluations) {
+                    // The search thread may have given up on the result
+                    let _ = reply.send(Ok(evaluation));
+                }
+            }
+            result => {
+                let err = match result {
+                    Ok(evaluations) => EvaluationError::WrongResultCount {
+                        positions: games.len(),
+                        returned: evaluations.len(),
+                    },
+                    Err(err) => err,
+                };
+                for reply in replies {
+                    let _ = reply.send(Err(err.clone()));
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::{BadukClassical, BadukMove, Board, Player, Point};
+    use rand::SeedableRng;
+    use std::sync::Barrier;
+
+    type Game = BadukClassical<9>;
+
+    // Each position holds one black stone, and the evaluator gives back its point as the policy
+    struct StoneEvaluator {
+        batches: Arc<Mutex<Vec<usize>>>,
+    }
+
+    impl Evaluator<Game> for StoneEvaluator {
+        fn evaluate_batch(
+            &mut self,
+            positions: &[Game],
+        ) -> Result<Vec<Evaluation<BadukMove>>, EvaluationError> {
+            self.batches.lock().unwrap().push(positions.len());
+            Ok(positions
+                .iter()
+                .map(|game| {
+                    let coordinates = (0..9)
+                        .flat_map(|r| (0..9).map(move |c| (r, c)))
+                        .find(|&(r, c)| {
+                            game.board.get_point(r, c) == Some(Point::Stone(Player::Black))
+                        })
+                        .unwrap();
+                    Evaluation {
+                        policy: vec![(BadukMove::Play { coordinates }, 1.0)],
+                        value: 0.5,
+                    }
+                })
+                .collect())
+        }
+    }
+
+    fn position(index: usize) -> Game {
+        let mut board = Board::new();
+        board.place_stone(index / 9, index % 9, Player::Black);
+        Game::from_position(board, Player::White)
+    }
+
+    // Submits one position from each of `threads` threads at once, and returns the policy move
+    // each thread got back with the sizes of the batches run
+    fn submit(threads: usize, config: BatchConfig) -> (Vec<BadukMove>, Vec<usize>) {
+        let batches = Arc::new(Mutex::new(Vec::new()));
+        let queue = EvaluationQueue::new(
+            StoneEvaluator {
+                batches: batches.clone(),
+            },
+            config,
+        );
+        let start = Barrier::new(threads);
+        let moves = std::thread::scope(|scope| {
+            let handles: Vec<_> = (0..threads)
+                .map(|index| {
+                    let (queue, start) = (&queue, &start);
+                    scope.spawn(move || {
+                        start.wait();
+                        queue.evaluate(&position(index)).unwrap().policy[0].0
+                    })
+                })
+                .collect();
+            handles
+                .into_iter()
+                .map(|handle| handle.join().unwrap())
+                .collect()
+        });
+        drop(queue);
+        let batches = batches.lock().unwrap().clone();
+        (moves, batches)
+    }
+
+    #[test]
+    fn full_batches_run_without_waiting() {
+        let started = Instant::now();
+        let (_, batches) = submit(
+            8,
+            BatchConfig {
+                batch_size: 4,
+                max_wait: Duration::from_secs(30),
+            },
+        );
+        assert_eq!(batches, vec![4, 4]);
+        assert!(started.elapsed() < Duration::from_secs(30));
+    }
+
+    #[test]
+    fn batches_run_once_the_wait_is_over() {
+        let max_wait = Duration::from_millis(100);
+        let started = Instant::now();
+        let (_, batches) = submit(
+            3,
+            BatchConfig {
+                batch_size: 16,
+                max_wait,
+            },
+        );
+        assert_eq!(batches.iter().sum::<usize>(), 3);
+        assert!(batches.iter().all(|&size| size < 16));
+        assert!(started.elapsed() >= max_wait);
+    }
+
+    #[test]
+    fn every_caller_gets_its_own_evaluation() {
+        let (moves, batches) = submit(
+            12,
+            BatchConfig {
+                batch_size: 5,
+                max_wait: Duration::from_millis(20),
+            },
+        );
+        assert_eq!(batches.iter().sum::<usize>(), 12);
+        for (index, mv) in moves.into_iter().enumerate() {
+            assert_eq!(
+                mv,
+                BadukMove::Play {
+                    coordinates: (index / 9, index % 9)
+                }
+            );
+        }
+    }
+
+    #[test]
+    fn playout_evaluations_favour_the_player_ahead() {
+        let mut evaluator = PlayoutEvaluator::new(
+            PlayoutPolicy::Reasonable,
+            Arc::default(),
+            -100.0,
+            81,
+            ChaCha8Rng::seed_from_u64(3),
+        );
+        let game = Game::new();
+        let evaluations = evaluator
+            .evaluate_batch(&[game.clone(), game.clone()])
+            .unwrap();
+        assert_eq!(evaluations.len(), 2);
+        for evaluation in evaluations {
+            assert_eq!(evaluation.value, 1.0);
+            let total: f32 = evaluation.policy.iter().map(|(_, prior)| prior).sum();
+            assert!((total - 1.0).abs() < 1e-5);
+            assert!(evaluation.policy.iter().all(|(mv, _)| game.is_legal(mv)));
+        }
+    }
+}
@@ -1,5 +1,9 @@
 use indexmap::IndexMap;
+use rand::seq::SliceRandom;
+use rand::Rng;
+use std::fmt;
 use std::hash::Hash;
+use std::str::FromStr;
 use thiserror::Error;
 
 #[derive(Clone, Copy, PartialEq, Eq, Debug, Hash)]
@@ -23,11 +27,68 @@ pub enum Point {
     Stone(Player),
 }
 
-#[derive(Clone, Debug)]
+/// One of the eight ways to rotate or mirror a square board onto itself.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Hash)]
+pub enum Symmetry {
+    Identity,
+    Rotate90,
+    Rotate180,
+    Rotate270,
+    FlipHorizontal,
+    FlipVertical,
+    Transpose,
+    AntiTranspose,
+}
+
+impl Symmetry {
+    pub const ALL: [Symmetry; 8] = [
+        Symmetry::Identity,
+        Symmetry::Rotate90,
+        Symmetry::Rotate180,
+        Symmetry::Rotate270,
+        Symmetry::FlipHorizontal,
+        Symmetry::FlipVertical,
+        Symmetry::Transpose,
+        Symmetry::AntiTranspose,
+    ];
+
+    /// Where the point (row, column) ends up on a board of the given size. Rotations are
+    /// clockwise.
+    pub fn apply(self, (r, c): (usize, usize), size: usize) -> (usize, usize) {
+        let last = size - 1;
+        match self {
+            Symmetry::Identity => (r, c),
+            Symmetry::Rotate90 => (c, last - r),
+            Symmetry::Rotate180 => (last - r, last - c),
+            Symmetry::Rotate270 => (last - c, r),
+            Symmetry::FlipHorizontal => (r, last - c),
+            Symmetry::FlipVertical => (last - r, c),
+            Symmetry::Transpose => (c, r),
+            Symmetry::AntiTranspose => (last - c, last - r),
+        }
+    }
+
+    /// The symmetry that undoes this one.
+    pub fn inverse(self) -> Symmetry {
+        match self {
+            Symmetry::Rotate90 => Symmetry::Rotate270,
+            Symmetry::Rotate270 => Symmetry::Rotate90,
+            other => other,
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, Debug)]
 pub struct Board<const SIZE: usize> {
     points: [[Point; SIZE]; SIZE],
 }
 
+impl<const SIZE: usize> Default for Board<SIZE> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl<const SIZE: usize> Board<SIZE> {
     pub fn new() -> Self {
         Self {
@@ -131,18 +192,129 @@ impl<const SIZE: usize> Board<SIZE> {
 
         liberties.len()
     }
+
+    /// Whether the empty point is an eye of `player`: all its neighbours are their stones and the
+    /// opponent holds at most one diagonal point, or none on the edge of the board.
+    pub fn is_eye(&self, r: usize, c: usize, player: Player) -> bool {
+        if self.get_point(r, c) != Some(Point::Empty) {
+            return false;
+        }
+        let surrounded = self
+            .get_adjacent_points(r, c)
+            .into_iter()
+            .all(|(adj_r, adj_c)| self.get_point(adj_r, adj_c) == Some(Point::Stone(player)));
+        if !surrounded {
+            return false;
+        }
+
+        let diagonals: Vec<Point> = [(-1, -1), (-1, 1), (1, -1), (1, 1)]
+            .into_iter()
+            .filter_map(|(dr, dc)| {
+                let diagonal_r = r.checked_add_signed(dr)?;
+                let diagonal_c = c.checked_add_signed(dc)?;
+                self.get_point(diagonal_r, diagonal_c)
+            })
+            .collect();
+        let opponent_diagonals = diagonals
+            .iter()
+            .filter(|point| **point == Point::Stone(player.opponent()))
+            .count();
+        if diagonals.len() < 4 {
+            opponent_diagonals == 0
+        } else {
+            opponent_diagonals <= 1
+        }
+    }
+
+    /// A Zobrist hash of the stones on the board.
+    pub fn zobrist_hash(&self) -> u64 {
+        let mut hash = 0;
+        for (r, row) in self.points.iter().enumerate() {
+            for (c, point) in row.iter().enumerate() {
+                if let Point::Stone(player) = point {
+                    let colour = match player {
+                        Player::Black => 0,
+                        Player::White => 1,
+                    };
+                    hash ^= zobrist_key(((r * SIZE + c) * 2 + colour) as u64 + ZOBRIST_POINTS);
+                }
+            }
+        }
+        hash
+    }
+
+    /// The board rotated or mirrored.
+    pub fn transformed(&self, symmetry: Symmetry) -> Self {
+        Self {
+            points: transform_points(&self.points, symmetry),
+        }
+    }
+
+    /// The board with every black stone white and every white stone black.
+    pub fn with_colours_swapped(&self) -> Self {
+        Self {
+            points: swap_point_colours(&self.points),
+        }
+    }
+}
+
+fn transform_points<const SIZE: usize>(
+    points: &[[Point; SIZE]; SIZE],
+    symmetry: Symmetry,
+) -> [[Point; SIZE]; SIZE] {
+    let mut transformed = [[Point::Empty; SIZE]; SIZE];
+    for (r, row) in points.iter().enumerate() {
+        for (c, point) in row.iter().enumerate() {
+            let (new_r, new_c) = symmetry.apply((r, c), SIZE);
+            transformed[new_r][new_c] = *point;
+        }
+    }
+    transformed
+}
+
+fn swap_point_colours<const SIZE: usize>(points: &[[Point; SIZE]; SIZE]) -> [[Point; SIZE]; SIZE] {
+    points.map(|row| {
+        row.map(|point| match point {
+            Point::Stone(player) => Point::Stone(player.opponent()),
+            Point::Empty => Point::Empty,
+        })
+    })
+}
+
+// Keys below this offset are left for what the game adds to the board hash, such as the turn
+const ZOBRIST_POINTS: u64 = 1 << 16;
+
+/// The random number for one feature of a position in a Zobrist hash. It is derived from the
+/// index with SplitMix64, so hashes are the same in every run and on every platform.
+pub fn zobrist_key(index: u64) -> u64 {
+    let mut z = index.wrapping_add(1).wrapping_mul(0x9e37_79b9_7f4a_7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
 }
 
-#[derive(Clone, Debug)]
+pub type Territory = Vec<(usize, usize)>;
+
+pub const DEFAULT_KOMI: f32 = 6.5;
+
+#[derive(Clone, PartialEq, Debug)]
 pub struct BadukClassical<const SIZE: usize> {
     pub turn: Player,
     pub board: Board<SIZE>,
     pub captures: (u32, u32), // (black, white)
     pub ko_point: Option<(usize, usize)>,
     pub consecutive_passes: u8,
+    pub resigned: Option<Player>,
+    /// The position after every move, starting with the initial position.
     pub position_history: Vec<[[Point; SIZE]; SIZE]>,
 }
 
+impl<const SIZE: usize> Default for BadukClassical<SIZE> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 pub enum SupportedGames {
     BadukClassic(BadukClassical<19>),
     BadukBeginner(BadukClassical<13>),
@@ -188,10 +360,80 @@ impl<const SIZE: usize> BadukClassical<SIZE> {
             captures: (0, 0),
             ko_point: None,
             consecutive_passes: 0,
+            resigned: None,
             position_history: vec![[[Point::Empty; SIZE]; SIZE]],
         }
     }
 
+    /// The same game with every position, including the earlier ones and the ko point, rotated
+    /// or mirrored.
+    pub fn transformed(&self, symmetry: Symmetry) -> Self {
+        Self {
+            board: self.board.transformed(symmetry),
+            ko_point: self.ko_point.map(|point| symmetry.apply(point, SIZE)),
+            position_history: self
+                .position_history
+                .iter()
+                .map(|points| transform_points(points, symmetry))
+                .collect(),
+            ..self.clone()
+        }
+    }
+
+    /// The same game with the colours swapped, so the other player is to move.
+    pub fn with_colours_swapped(&self) -> Self {
+        Self {
+            turn: self.turn.opponent(),
+            board: self.board.with_colours_swapped(),
+            captures: (self.captures.1, self.captures.0),
+            resigned: self.resigned.map(|player| player.opponent()),
+            position_history: self
+                .position_history
+                .iter()
+                .map(swap_point_colours)
+                .collect(),
+            ..self.clone()
+        }
+    }
+
+    /// A position hash that is the same however the board is rotated or mirrored, and the
+    /// symmetry that turns the game into its canonical orientation. Moves stored under the hash
+    /// are transformed with that symmetry, and back with its inverse.
+    pub fn canonical_hash(&self) -> (u64, Symmetry) {
+        Symmetry::ALL
+            .into_iter()
+            .map(|symmetry| (self.transformed(symmetry).position_hash(), symmetry))
+            .min_by_key(|(hash, _)| *hash)
+            .expect("There are eight symmetries")
+    }
+
+    /// The position and the move played in it in all eight orientations, for augmenting
+    /// training data. Orientations that are the same because the position is symmetric are only
+    /// given once.
+    pub fn symmetric_samples(&self, mv: BadukMove) -> Vec<(Self, BadukMove)> {
+        let mut samples: Vec<(Self, BadukMove)> = Vec::with_capacity(Symmetry::ALL.len());
+        for symmetry in Symmetry::ALL {
+            let sample = (self.transformed(symmetry), mv.transformed(symmetry, SIZE));
+            if !samples.contains(&sample) {
+                samples.push(sample);
+            }
+        }
+        samples
+    }
+
+    /// Starts a game from a set up position, such as handicap stones or a problem diagram.
+    pub fn from_position(board: Board<SIZE>, turn: Player) -> Self {
+        Self {
+            turn,
+            position_history: vec![board.points],
+            board,
+            captures: (0, 0),
+            ko_point: None,
+            consecutive_passes: 0,
+            resigned: None,
+        }
+    }
+
     pub fn remove_captured_stones(&mut self, opponent: Player) -> u32 {
         let mut captured_count = 0;
         let mut stones_to_remove = Vec::new();
@@ -251,10 +493,15 @@ impl<const SIZE: usize> BadukClassical<SIZE> {
     }
 
     pub fn would_repeat_position(&self, r: usize, c: usize, player: Player) -> bool {
+        self.repeated_position(r, c, player).is_some()
+    }
+
+    /// The number of the move after which the position resulting from this play first occurred.
+    pub fn repeated_position(&self, r: usize, c: usize, player: Player) -> Option<usize> {
         let mut temp_board = self.board.clone();
 
         if !temp_board.place_stone(r, c, player) {
-            return true;
+            return None;
         }
 
         // Simulate captures
@@ -273,30 +520,35 @@ impl<const SIZE: usize> BadukClassical<SIZE> {
         }
 
         // Check against position history
-        self.position_history.contains(&temp_board.points)
+        self.position_history
+            .iter()
+            .position(|points| *points == temp_board.points)
     }
 
     pub fn is_game_over(&self) -> bool {
-        self.consecutive_passes >= 2
+        self.consecutive_passes >= 2 || self.resigned.is_some()
     }
 
     pub fn make_move(&mut self, mv: BadukMove) -> Result<(), MoveError> {
+        if self.is_game_over() {
+            return Err(MoveError::GameOver);
+        }
         match mv {
+            BadukMove::Resign => {
+                self.resigned = Some(self.turn);
+                Ok(())
+            }
             BadukMove::Pass => {
                 self.consecutive_passes += 1;
                 self.turn = self.turn.opponent();
                 self.ko_point = None;
+                self.position_history.push(self.board.points);
                 Ok(())
             }
             BadukMove::Play {
                 coordinates: (r, c),
             } => {
-                if !self.is_legal_move(r, c) {
-                    return Err(MoveError::IllegalMove);
-                }
-
-                // Save current position to history
-                self.position_history.push(self.board.points);
+                self.check_play(r, c)?;
 
                 // Place the stone
                 self.board.place_stone(r, c, self.turn);
@@ -333,6 +585,9 @@ impl<const SIZE: usize> BadukClassical<SIZE> {
                     None
                 };
 
+                // Save the new position to history
+                self.position_history.push(self.board.points);
+
                 // Switch turns
                 self.turn = self.turn.opponent();
 
@@ -341,37 +596,58 @@ impl<const SIZE: usize> BadukClassical<SIZE> {
         }
     }
 
+    /// Checks whether `player` may play `mv` now, explaining why not.
+    pub fn check_move(&self, player: Player, mv: &BadukMove) -> Result<(), MoveError> {
+        if self.is_game_over() {
+            return Err(MoveError::GameOver);
+        }
+        if player != self.turn {
+            return Err(MoveError::NotYourTurn { to_play: self.turn });
+        }
+        match mv {
+            BadukMove::Play {
+                coordinates: (r, c),
+            } => self.check_play(*r, *c),
+            BadukMove::Pass | BadukMove::Resign => Ok(()),
+        }
+    }
+
     fn is_legal_move(&self, r: usize, c: usize) -> bool {
+        self.check_play(r, c).is_ok()
+    }
+
+    fn check_play(&self, r: usize, c: usize) -> Result<(), MoveError> {
+        if self.is_game_over() {
+            return Err(MoveError::GameOver);
+        }
+
         // Check if position is empty
-        if self.board.get_point(r, c) != Some(Point::Empty) {
-            return false;
+        match self.board.get_point(r, c) {
+            None => return Err(MoveError::OffBoard { point: (r, c) }),
+            Some(Point::Stone(_)) => return Err(MoveError::Occupied { point: (r, c) }),
+            Some(Point::Empty) => {}
         }
 
         // Check ko rule
         if self.ko_point == Some((r, c)) {
-            return false;
+            return Err(MoveError::Ko { point: (r, c) });
         }
 
         // Check suicide rule
         if self.would_be_suicide(r, c, self.turn) {
-            return false;
+            return Err(MoveError::Suicide { point: (r, c) });
         }
 
         // Check position repetition
-        if self.would_repeat_position(r, c, self.turn) {
-            return false;
+        if let Some(move_number) = self.repeated_position(r, c, self.turn) {
+            return Err(MoveError::Superko { move_number });
         }
 
-        true
+        Ok(())
     }
 
-    pub fn calculate_territory(
-        &self,
-    ) -> (
-        Vec<(usize, usize)>,
-        Vec<(usize, usize)>,
-        Vec<(usize, usize)>,
-    ) {
+    /// Returns the (black, white, neutral) empty points.
+    pub fn calculate_territory(&self) -> (Territory, Territory, Territory) {
         let mut visited = std::collections::HashSet::new();
         let mut black_territory = Vec::new();
         let mut white_territory = Vec::new();
@@ -435,7 +711,7 @@ impl<const SIZE: usize> BadukClassical<SIZE> {
         }
     }
 
-    pub fn calculate_score(&self) -> (f32, f32) {
+    pub fn calculate_score(&self, komi: f32) -> (f32, f32) {
         let (black_territory, white_territory, _) = self.calculate_territory();
 
         // Count stones on board
@@ -453,38 +729,181 @@ impl<const SIZE: usize> BadukClassical<SIZE> {
         }
 
         let black_score = black_stones as f32 + black_territory.len() as f32;
-        let white_score = white_stones as f32 + white_territory.len() as f32 + 6.5; // 6.5 komi
+        let white_score = white_stones as f32 + white_territory.len() as f32 + komi;
 
         (black_score, white_score)
     }
 
-    pub fn get_winner(&self) -> Option<Player> {
+    /// How the game ended, or `None` while it is still being played. A game ended by passing is
+    /// decided by area score.
+    pub fn result(&self, komi: f32) -> Option<GameResult> {
+        if let Some(loser) = self.resigned {
+            return Some(GameResult::Resignation {
+                winner: loser.opponent(),
+            });
+        }
         if !self.is_game_over() {
             return None;
         }
 
-        let (black_score, white_score) = self.calculate_score();
-
-        if black_score > white_score {
-            Some(Player::Black)
+        let (black_score, white_score) = self.calculate_score(komi);
+        let result = if black_score > white_score {
+            GameResult::Score {
+                winner: Player::Black,
+                margin: black_score - white_score,
+            }
         } else if white_score > black_score {
-            Some(Player::White)
+            GameResult::Score {
+                winner: Player::White,
+                margin: white_score - black_score,
+            }
         } else {
-            None // Draw
+            GameResult::Draw
+        };
+        Some(result)
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum GameResult {
+    Score {
+        winner: Player,
+        margin: f32,
+    },
+    Resignation {
+        winner: Player,
+    },
+    Time {
+        winner: Player,
+    },
+    Forfeit {
+        winner: Player,
+    },
+    /// Jigo, both players have the same score.
+    Draw,
+    /// No result, e.g. the game was suspended and never finished.
+    Void,
+}
+
+impl GameResult {
+    pub fn winner(&self) -> Option<Player> {
+        match *self {
+            GameResult::Score { winner, .. }
+            | GameResult::Resignation { winner }
+            | GameResult::Time { winner }
+            | GameResult::Forfeit { winner } => Some(winner),
+            GameResult::Draw | GameResult::Void => None,
         }
     }
 }
 
+/// Formats the result as written in game records: "B+3.5", "W+R", "B+T", "W+F", "0" or "Void".
+impl fmt::Display for GameResult {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let colour = |winner: Player| match winner {
+            Player::Black => "B",
+            Player::White => "W",
+        };
+        match *self {
+            GameResult::Score { winner, margin } => write!(f, "{}+{margin}", colour(winner)),
+            GameResult::Resignation { winner } => write!(f, "{}+R", colour(winner)),
+            GameResult::Time { winner } => write!(f, "{}+T", colour(winner)),
+            GameResult::Forfeit { winner } => write!(f, "{}+F", colour(winner)),
+            GameResult::Draw => write!(f, "0"),
+            GameResult::Void => write!(f, "Void"),
+        }
+    }
+}
+
+#[derive(Error, Debug)]
+#[error("Invalid Game Result \"{0}\"")]
+pub struct ParseResultError(String);
+
+/// Parses results as written in game records, also accepting the long forms "B+Resign",
+/// "W+Time", "B+Forfeit", "Draw" and "Jigo".
+impl FromStr for GameResult {
+    type Err = ParseResultError;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let error = || ParseResultError(text.to_string());
+        let text = text.trim();
+        if text == "0" || text.eq_ignore_ascii_case("draw") || text.eq_ignore_ascii_case("jigo") {
+            return Ok(GameResult::Draw);
+        }
+        if text.eq_ignore_ascii_case("void") {
+            return Ok(GameResult::Void);
+        }
+
+        let (colour, reason) = text.split_once('+').ok_or_else(error)?;
+        let winner = match colour {
+            "B" | "b" => Player::Black,
+            "W" | "w" => Player::White,
+            _ => return Err(error()),
+        };
+        let result = match reason {
+            "R" | "Resign" => GameResult::Resignation { winner },
+            "T" | "Time" => GameResult::Time { winner },
+            "F" | "Forfeit" => GameResult::Forfeit { winner },
+            margin => GameResult::Score {
+                winner,
+                margin: margin.parse().map_err(|_| error())?,
+            },
+        };
+        Ok(result)
+    }
+}
+
 #[derive(Clone, Copy, Debug, Hash, PartialEq, Eq)]
 pub enum BadukMove {
     Play { coordinates: (usize, usize) },
     Pass,
+    Resign,
+}
+
+impl BadukMove {
+    /// The same move on the rotated or mirrored board.
+    pub fn transformed(self, symmetry: Symmetry, size: usize) -> Self {
+        match self {
+            BadukMove::Play { coordinates } => BadukMove::Play {
+                coordinates: symmetry.apply(coordinates, size),
+            },
+            other => other,
+        }
+    }
+}
+
+/// Go board coordinates as written in game records, e.g. "Q16". The column letters skip I.
+pub fn move_label(mv: &BadukMove) -> String {
+    match mv {
+        BadukMove::Pass => "Pass".to_string(),
+        BadukMove::Resign => "Resign".to_string(),
+        BadukMove::Play {
+            coordinates: (r, c),
+        } => {
+            let column = (b'A' + *c as u8 + u8::from(*c >= 8)) as char;
+            format!("{column}{}", r + 1)
+        }
+    }
 }
 
 #[derive(Error, Debug)]
 pub enum MoveError {
-    #[error("Illegal Move")]
-    IllegalMove,
+    #[error("That Point Is Already Occupied")]
+    Occupied { point: (usize, usize) },
+    #[error("That Point Is Off the Board")]
+    OffBoard { point: (usize, usize) },
+    #[error("Ko: the Stone Cannot Be Retaken Immediately")]
+    Ko { point: (usize, usize) },
+    #[error("Superko: This Repeats the Position After Move {move_number}")]
+    Superko { move_number: usize },
+    #[error("Suicide: the Stone Would Have No Liberties")]
+    Suicide { point: (usize, usize) },
+    #[error("It Is {to_play:?}'s Turn")]
+    NotYourTurn { to_play: Player },
+    #[error("The Game Is Over")]
+    GameOver,
+    #[error("There Are No Legal Moves")]
+    NoLegalMoves,
     #[error("Unexpected Missing Move in Game Tree")]
     MissingMove,
 }
@@ -494,12 +913,30 @@ pub trait StatelessGame: Sized + Clone {
     fn list_all_legal_moves(&self) -> Vec<Self::Move>;
     fn is_legal(&self, game_move: &Self::Move) -> bool;
     fn generate_next_board(&self, game_move: &Self::Move) -> Result<Self, MoveError>;
+    fn turn(&self) -> Player;
+    fn is_game_over(&self) -> bool;
+    /// Who is ahead, judging the position as if the game ended now. `None` for a draw.
+    fn winner(&self, komi: f32) -> Option<Player>;
+    /// A hash of everything that decides how the game goes on from here, the same whichever moves
+    /// led to the position.
+    fn position_hash(&self) -> u64;
+
+    /// A random move that is not obviously bad, for random play and playouts. `None` if there are
+    /// no legal moves. Any legal move will do unless the game knows better.
+    fn random_reasonable_move<R: Rng>(&self, rng: &mut R) -> Option<Self::Move> {
+        self.list_all_legal_moves().choose(rng).copied()
+    }
 }
 
 impl<const SIZE: usize> StatelessGame for BadukClassical<SIZE> {
     type Move = BadukMove;
 
+    // Resigning is always legal while the game is on, but it is left out here so that searching
+    // bots only consider moves on the board
     fn list_all_legal_moves(&self) -> Vec<Self::Move> {
+        if self.is_game_over() {
+            return Vec::new();
+        }
         let mut moves = Vec::new();
 
         // Always allow pass
@@ -521,7 +958,7 @@ impl<const SIZE: usize> StatelessGame for BadukClassical<SIZE> {
 
     fn is_legal(&self, game_move: &Self::Move) -> bool {
         match game_move {
-            BadukMove::Pass => true,
+            BadukMove::Pass | BadukMove::Resign => !self.is_game_over(),
             BadukMove::Play {
                 coordinates: (r, c),
             } => self.is_legal_move(*r, *c),
@@ -533,11 +970,103 @@ impl<const SIZE: usize> StatelessGame for BadukClassical<SIZE> {
         next_game.make_move(*game_move)?;
         Ok(next_game)
     }
+
+    fn turn(&self) -> Player {
+        self.turn
+    }
+
+    fn is_game_over(&self) -> bool {
+        BadukClassical::is_game_over(self)
+    }
+
+    fn winner(&self, komi: f32) -> Option<Player> {
+        if let Some(loser) = self.resigned {
+            return Some(loser.opponent());
+        }
+        let (black_score, white_score) = self.calculate_score(komi);
+        if black_score > white_score {
+            Some(Player::Black)
+        } else if white_score > black_score {
+            Some(Player::White)
+        } else {
+            None
+        }
+    }
+
+    // The board, the player to move, the ko point and the passes so far, but not the earlier
+    // positions, so a superko can rarely make two positions with the same hash play differently
+    fn position_hash(&self) -> u64 {
+        let mut hash = self.board.zobrist_hash();
+        if self.turn == Player::White {
+            hash ^= zobrist_key(0);
+        }
+        if let Some((r, c)) = self.ko_point {
+            hash ^= zobrist_key(1 + (r * SIZE + c) as u64);
+        }
+        if self.consecutive_passes > 0 {
+            hash ^= zobrist_key((1 + SIZE * SIZE) as u64 + u64::from(self.consecutive_passes));
+        }
+        hash
+    }
+
+    // Plays anywhere but in its own eyes and only passes once nothing else is left, so random games
+    // end with the territory settled instead of a random pass
+    fn random_reasonable_move<R: Rng>(&self, rng: &mut R) -> Option<BadukMove> {
+        let mut empty_points: Vec<(usize, usize)> = (0..SIZE)
+            .flat_map(|r| (0..SIZE).map(move |c| (r, c)))
+            .filter(|&(r, c)| self.board.get_point(r, c) == Some(Point::Empty))
+            .collect();
+        empty_points.shuffle(rng);
+        let play = empty_points
+            .into_iter()
+            .find(|&(r, c)| !self.board.is_eye(r, c, self.turn) && self.is_legal_move(r, c));
+        Some(play.map_or(BadukMove::Pass, |coordinates| BadukMove::Play {
+            coordinates,
+        }))
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum MoveEvaluation {
+    Good,
+    Bad,
+    Doubtful,
+    Interesting,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum PositionJudgement {
+    GoodForBlack,
+    GoodForWhite,
+    Even,
+    Unclear,
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum Markup {
+    Triangle((usize, usize)),
+    Square((usize, usize)),
+    Circle((usize, usize)),
+    Cross((usize, usize)),
+    Label((usize, usize), String),
+    Arrow((usize, usize), (usize, usize)),
+    Line((usize, usize), (usize, usize)),
+    Dimmed((usize, usize)),
+}
+
+/// Review notes attached to a position, the move evaluation judges the move leading to it.
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+pub struct NodeAnnotations {
+    pub comment: Option<String>,
+    pub move_evaluation: Option<MoveEvaluation>,
+    pub position_judgement: Option<PositionJudgement>,
+    pub markup: Vec<Markup>,
 }
 
 #[derive(Clone)]
 pub struct GameNode<G: StatelessGame> {
     pub game: G,
+    pub annotations: NodeAnnotations,
     // Maybe its a good idea to swap this out for a hashmap, but I think since most of these are
     // going to only contain 5-10 elements it might be faster as a btree, and it also ensures that
     // they can be displayed and saved in a consistent order.
@@ -545,62 +1074,488 @@ pub struct GameNode<G: StatelessGame> {
     // Also the game G gets saved twice, once as the key in the btree and another as the
     // GameNode.game.
     pub children: IndexMap<G::Move, GameNode<G>>,
+    // The child the game was last continued with, so redo returns to the variation it came from.
+    last_visited: Option<G::Move>,
 }
 impl<G: StatelessGame> GameNode<G> {
-    fn new(game: G) -> Self {
+    pub fn new(game: G) -> Self {
         GameNode {
             game,
+            annotations: NodeAnnotations::default(),
             children: IndexMap::new(),
+            last_visited: None,
         }
     }
     fn traverse_downward(&mut self, mv: &G::Move) -> Result<&mut Self, MoveError> {
         self.children.get_mut(mv).ok_or(MoveError::MissingMove)
     }
 
-    fn make_move(&mut self, mv: G::Move) -> Result<&mut Self, MoveError> {
-        if !self.game.is_legal(&mv) {
-            return Err(MoveError::IllegalMove);
+    fn child(&self, mv: &G::Move) -> Result<&Self, MoveError> {
+        self.children.get(mv).ok_or(MoveError::MissingMove)
+    }
+
+    /// The first child is the main line continuation, later children are variations.
+    pub fn main_child(&self) -> Option<(&G::Move, &Self)> {
+        self.children.first()
+    }
+
+    pub fn last_visited_child(&self) -> Option<(&G::Move, &Self)> {
+        let mv = self.last_visited.as_ref()?;
+        self.children.get_key_value(mv)
+    }
+
+    pub fn make_move(&mut self, mv: G::Move) -> Result<&mut Self, MoveError> {
+        if !self.children.contains_key(&mv) {
+            let next_board = self.game.generate_next_board(&mv)?;
+            self.children.insert(mv, GameNode::new(next_board));
         }
-        let generate_board = || {
-            let current_board = &self.game;
-            let next_board = current_board.generate_next_board(&mv).unwrap();
-            GameNode::new(next_board)
-        };
-        let next_node_ref = self.children.entry(mv).or_insert_with(generate_board);
-        Ok(next_node_ref)
+        self.traverse_downward(&mv)
     }
 }
 
 pub struct GamePointer<G: StatelessGame> {
     move_list: Vec<G::Move>,
 }
+impl<G: StatelessGame> Default for GamePointer<G> {
+    fn default() -> Self {
+        GamePointer {
+            move_list: Vec::new(),
+        }
+    }
+}
 impl<G: StatelessGame> GamePointer<G> {
-    fn traverse_tree_downward<'a>(
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn moves(&self) -> &[G::Move] {
+        &self.move_list
+    }
+
+    pub fn node<'a>(&self, game: &'a GameTree<G>) -> Result<&'a GameNode<G>, MoveError> {
+        game.node(&self.move_list)
+    }
+
+    pub fn traverse_tree_downward<'a>(
         &self,
         game: &'a mut GameTree<G>,
     ) -> Result<&'a mut GameNode<G>, MoveError> {
-        let mut node = &mut game.root;
-        for mv in &self.move_list {
-            let next = node.traverse_downward(mv)?;
-            node = next;
-        }
-        Ok(node)
+        game.node_mut(&self.move_list)
     }
-    fn make_move<'a>(
+    pub fn make_move<'a>(
         &mut self,
         game: &'a mut GameTree<G>,
         mv: G::Move,
     ) -> Result<&'a mut GameNode<G>, MoveError> {
         let active_board = self.traverse_tree_downward(game)?;
-        let next_board = active_board.make_move(mv)?;
+        active_board.make_move(mv)?;
+        active_board.last_visited = Some(mv);
         self.move_list.push(mv);
-        Ok(next_board)
+        active_board.traverse_downward(&mv)
     }
-    fn undo_move(&mut self) {
+    pub fn undo_move(&mut self) {
         let _ = self.move_list.pop();
     }
+
+    /// Steps forward to the child that was visited last, or along the main line if there is none.
+    pub fn redo<'a>(&mut self, game: &'a GameTree<G>) -> Result<&'a GameNode<G>, MoveError> {
+        let node = self.node(game)?;
+        let (mv, next) = node
+            .last_visited_child()
+            .or_else(|| node.main_child())
+            .ok_or(MoveError::MissingMove)?;
+        self.move_list.push(*mv);
+        Ok(next)
+    }
+
+    /// Steps forward along the main line.
+    pub fn forward<'a>(&mut self, game: &'a GameTree<G>) -> Result<&'a GameNode<G>, MoveError> {
+        let (mv, next) = self
+            .node(game)?
+            .main_child()
+            .ok_or(MoveError::MissingMove)?;
+        self.move_list.push(*mv);
+        Ok(next)
+    }
+
+    pub fn go_to_start(&mut self) {
+        self.move_list.clear();
+    }
+
+    /// Follows the main line from the current node down to its last move.
+    pub fn go_to_end(&mut self, game: &GameTree<G>) {
+        while self.forward(game).is_ok() {}
+    }
+
+    /// Jumps to the node reached by `moves`, leaving the pointer untouched if it is not in the tree.
+    pub fn go_to(&mut self, game: &mut GameTree<G>, moves: Vec<G::Move>) -> Result<(), MoveError> {
+        game.node(&moves)?;
+
+        let mut node = &mut game.root;
+        for mv in &moves {
+            node.last_visited = Some(*mv);
+            node = node.traverse_downward(mv)?;
+        }
+        self.move_list = moves;
+        Ok(())
+    }
+
+    /// The other moves that were tried instead of the current one. The root has no siblings.
+    pub fn siblings<'a>(&self, game: &'a GameTree<G>) -> Result<Vec<&'a G::Move>, MoveError> {
+        let Some((current, parent_moves)) = self.move_list.split_last() else {
+            return Ok(Vec::new());
+        };
+        let parent = game.node(parent_moves)?;
+        Ok(parent.children.keys().filter(|mv| *mv != current).collect())
+    }
+
+    /// Deletes the current node with all its variations and steps back to its parent.
+    pub fn delete_current(&mut self, game: &mut GameTree<G>) -> Result<GameNode<G>, MoveError> {
+        let deleted = game.delete_subtree(&self.move_list)?;
+        self.move_list.pop();
+        Ok(deleted)
+    }
+}
+
+/// Who played the game and under which conditions, as recorded in the game record header.
+#[derive(Clone, PartialEq, Debug)]
+pub struct GameInfo {
+    pub black_player: Option<String>,
+    pub white_player: Option<String>,
+    pub black_rank: Option<String>,
+    pub white_rank: Option<String>,
+    pub event: Option<String>,
+    pub round: Option<String>,
+    pub date: Option<String>,
+    pub place: Option<String>,
+    pub rules: Option<String>,
+    pub komi: f32,
+    pub handicap: u32,
+    /// Main time per player in seconds.
+    pub main_time: Option<f32>,
+    /// The overtime system, e.g. "5x30 byo-yomi".
+    pub overtime: Option<String>,
+    pub result: Option<GameResult>,
+}
+
+impl Default for GameInfo {
+    fn default() -> Self {
+        GameInfo {
+            black_player: None,
+            white_player: None,
+            black_rank: None,
+            white_rank: None,
+            event: None,
+            round: None,
+            date: None,
+            place: None,
+            rules: None,
+            komi: DEFAULT_KOMI,
+            handicap: 0,
+            main_time: None,
+            overtime: None,
+            result: None,
+        }
+    }
 }
 
 pub struct GameTree<Game: StatelessGame> {
+    pub info: GameInfo,
     root: GameNode<Game>,
 }
+impl<G: StatelessGame> GameTree<G> {
+    pub fn new(game: G) -> Self {
+        GameTree {
+            info: GameInfo::default(),
+            root: GameNode::new(game),
+        }
+    }
+
+    pub fn root(&self) -> &GameNode<G> {
+        &self.root
+    }
+
+    pub fn root_mut(&mut self) -> &mut GameNode<G> {
+        &mut self.root
+    }
+
+    pub fn node(&self, moves: &[G::Move]) -> Result<&GameNode<G>, MoveError> {
+        let mut node = &self.root;
+        for mv in moves {
+            node = node.child(mv)?;
+        }
+        Ok(node)
+    }
+
+    pub fn node_mut(&mut self, moves: &[G::Move]) -> Result<&mut GameNode<G>, MoveError> {
+        let mut node = &mut self.root;
+        for mv in moves {
+            node = node.traverse_downward(mv)?;
+        }
+        Ok(node)
+    }
+
+    /// The moves and positions of the main line, following the first child from the root.
+    pub fn main_line(&self) -> impl Iterator<Item = (&G::Move, &GameNode<G>)> {
+        std::iter::successors(self.root.main_child(), |(_, node)| node.main_child())
+    }
+
+    /// Makes every move along `moves` the first child of its parent, so the variation becomes the
+    /// main line.
+    pub fn promote_variation(&mut self, moves: &[G::Move]) -> Result<(), MoveError> {
+        self.node(moves)?;
+
+        let mut node = &mut self.root;
+        for mv in moves {
+            let index = node
+                .children
+                .get_index_of(mv)
+                .ok_or(MoveError::MissingMove)?;
+            node.children.move_index(index, 0);
+            node = node.traverse_downward(mv)?;
+        }
+        Ok(())
+    }
+
+    /// Removes the node reached by `moves` together with all of its variations. The root cannot be
+    /// deleted.
+    pub fn delete_subtree(&mut self, moves: &[G::Move]) -> Result<GameNode<G>, MoveError> {
+        let (mv, parent_moves) = moves.split_last().ok_or(MoveError::MissingMove)?;
+        let parent = self.node_mut(parent_moves)?;
+        if parent.last_visited.as_ref() == Some(mv) {
+            parent.last_visited = None;
+        }
+        parent
+            .children
+            .shift_remove(mv)
+            .ok_or(MoveError::MissingMove)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    type Game = BadukClassical<9>;
+
+    fn play(r: usize, c: usize) -> BadukMove {
+        BadukMove::Play {
+            coordinates: (r, c),
+        }
+    }
+
+    #[test]
+    fn inverse_symmetries_undo_the_symmetry() {
+        let game = [play(2, 3), play(6, 1), play(0, 8)]
+            .iter()
+            .fold(Game::new(), |game, mv| {
+                game.generate_next_board(mv).unwrap()
+            });
+        for symmetry in Symmetry::ALL {
+            for size in [4, 9] {
+                for point in (0..size).flat_map(|r| (0..size).map(move |c| (r, c))) {
+                    let transformed = symmetry.apply(point, size);
+                    assert_eq!(symmetry.inverse().apply(transformed, size), point);
+                }
+            }
+            assert_eq!(
+                game.transformed(symmetry).transformed(symmetry.inverse()),
+                game,
+                "{symmetry:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn canonical_hashes_are_the_same_in_every_orientation() {
+        let game = [play(2, 3), play(6, 1), play(0, 8)]
+            .iter()
+            .fold(Game::new(), |game, mv| {
+                game.generate_next_board(mv).unwrap()
+            });
+        let (hash, symmetry) = game.canonical_hash();
+        for other in Symmetry::ALL {
+            let transformed = game.transformed(other);
+            let (transformed_hash, to_canonical) = transformed.canonical_hash();
+            assert_eq!(transformed_hash, hash, "{other:?}");
+            assert_eq!(
+                transformed.transformed(to_canonical).board,
+                game.transformed(symmetry).board
+            );
+        }
+    }
+
+    #[test]
+    fn symmetric_positions_give_fewer_samples() {
+        let empty = Game::new();
+        assert_eq!(empty.symmetric_samples(play(4, 4)).len(), 1);
+        assert_eq!(empty.symmetric_samples(play(2, 2)).len(), 4);
+        let samples = empty.symmetric_samples(play(2, 3));
+        assert_eq!(samples.len(), 8);
+        for (sample, mv) in &samples {
+            assert_eq!(sample, &empty);
+            assert!(sample.is_legal(mv));
+        }
+    }
+
+    // A tree with the main line A B and the variation C after the first move:
+    //
+    //   root - A - B
+    //            \ C
+    fn tree_with_variation() -> GameTree<Game> {
+        let mut tree = GameTree::new(Game::new());
+        let mut pointer = GamePointer::new();
+        pointer.make_move(&mut tree, play(2, 2)).unwrap();
+        pointer.make_move(&mut tree, play(6, 6)).unwrap();
+        pointer.undo_move();
+        pointer.make_move(&mut tree, play(6, 2)).unwrap();
+        tree
+    }
+
+    fn main_line_moves(tree: &GameTree<Game>) -> Vec<BadukMove> {
+        tree.main_line().map(|(mv, _)| *mv).collect()
+    }
+
+    #[test]
+    fn redo_follows_the_last_visited_child() {
+        let mut tree = tree_with_variation();
+        let mut pointer = GamePointer::new();
+        pointer
+            .go_to(&mut tree, vec![play(2, 2), play(6, 2)])
+            .unwrap();
+        pointer.undo_move();
+
+        pointer.redo(&tree).unwrap();
+        assert_eq!(pointer.moves(), &[play(2, 2), play(6, 2)]);
+
+        pointer.undo_move();
+        pointer.forward(&tree).unwrap();
+        assert_eq!(pointer.moves(), &[play(2, 2), play(6, 6)]);
+    }
+
+    #[test]
+    fn redo_without_a_visited_child_follows_the_main_line() {
+        let mut tree = tree_with_variation();
+        tree.delete_subtree(&[play(2, 2), play(6, 2)]).unwrap();
+        let mut pointer = GamePointer::new();
+        pointer.redo(&tree).unwrap();
+        pointer.redo(&tree).unwrap();
+
+        assert_eq!(pointer.moves(), &[play(2, 2), play(6, 6)]);
+        assert!(pointer.redo(&tree).is_err());
+    }
+
+    #[test]
+    fn siblings_are_listed_in_the_order_they_were_played() {
+        let mut tree = tree_with_variation();
+        let mut pointer = GamePointer::new();
+        pointer.go_to(&mut tree, vec![play(2, 2)]).unwrap();
+        pointer.make_move(&mut tree, play(4, 4)).unwrap();
+
+        assert_eq!(
+            pointer.siblings(&tree).unwrap(),
+            vec![&play(6, 6), &play(6, 2)]
+        );
+        pointer.go_to_start();
+        assert!(pointer.siblings(&tree).unwrap().is_empty());
+    }
+
+    #[test]
+    fn promoting_a_variation_changes_the_main_line() {
+        let mut tree = tree_with_variation();
+        assert_eq!(main_line_moves(&tree), vec![play(2, 2), play(6, 6)]);
+
+        tree.promote_variation(&[play(2, 2), play(6, 2)]).unwrap();
+        assert_eq!(main_line_moves(&tree), vec![play(2, 2), play(6, 2)]);
+        assert!(tree.promote_variation(&[play(0, 0)]).is_err());
+    }
+
+    #[test]
+    fn deleting_the_current_node_moves_the_pointer_to_its_parent() {
+        let mut tree = tree_with_variation();
+        let mut pointer = GamePointer::new();
+        pointer
+            .go_to(&mut tree, vec![play(2, 2), play(6, 6)])
+            .unwrap();
+
+        let deleted = pointer.delete_current(&mut tree).unwrap();
+        assert_eq!(
+            deleted.game.board.get_point(6, 6),
+            Some(Point::Stone(Player::White))
+        );
+        assert_eq!(pointer.moves(), &[play(2, 2)]);
+        assert_eq!(main_line_moves(&tree), vec![play(2, 2), play(6, 2)]);
+        assert!(tree.delete_subtree(&[]).is_err());
+    }
+
+    #[test]
+    fn no_move_is_accepted_after_a_resignation() {
+        let mut game = Game::new();
+        game.make_move(play(2, 2)).unwrap();
+        game.make_move(BadukMove::Resign).unwrap();
+
+        for mv in [play(6, 6), BadukMove::Pass, BadukMove::Resign] {
+            assert!(!game.is_legal(&mv));
+            assert!(matches!(game.make_move(mv), Err(MoveError::GameOver)));
+        }
+        assert!(game.list_all_legal_moves().is_empty());
+
+        let mut tree = GameTree::new(Game::new());
+        let mut pointer = GamePointer::new();
+        pointer.make_move(&mut tree, BadukMove::Resign).unwrap();
+        assert!(pointer.make_move(&mut tree, BadukMove::Pass).is_err());
+    }
+
+    #[test]
+    fn no_move_is_accepted_after_two_passes() {
+        let mut game = Game::new();
+        game.make_move(BadukMove::Pass).unwrap();
+        game.make_move(BadukMove::Pass).unwrap();
+
+        assert!(matches!(
+            game.make_move(play(4, 4)),
+            Err(MoveError::GameOver)
+        ));
+        assert!(matches!(
+            game.make_move(BadukMove::Pass),
+            Err(MoveError::GameOver)
+        ));
+    }
+
+    #[test]
+    fn results_read_back_as_written() {
+        let results = [
+            GameResult::Score {
+                winner: Player::Black,
+                margin: 3.5,
+            },
+            GameResult::Score {
+                winner: Player::White,
+                margin: 12.0,
+            },
+            GameResult::Resignation {
+                winner: Player::White,
+            },
+            GameResult::Time {
+                winner: Player::Black,
+            },
+            GameResult::Forfeit {
+                winner: Player::White,
+            },
+            GameResult::Draw,
+            GameResult::Void,
+        ];
+        for result in results {
+            assert_eq!(result.to_string().parse::<GameResult>().unwrap(), result);
+        }
+        assert_eq!("B+3.5".parse::<GameResult>().unwrap().to_string(), "B+3.5");
+        assert_eq!(
+            "W+Resign".parse::<GameResult>().unwrap(),
+            GameResult::Resignation {
+                winner: Player::White
+            }
+        );
+        assert_eq!("Jigo".parse::<GameResult>().unwrap(), GameResult::Draw);
+        assert!("?".parse::<GameResult>().is_err());
+    }
+}
@@ -0,0 +1,406 @@
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+
+use crate::game::{
+    zobrist_key, BadukClassical, BadukMove, GameNode, GameTree, MoveEvaluation, Player, Point,
+    Symmetry,
+};
+use crate::sgf::{self, SgfError};
+
+#[derive(Error, Debug)]
+pub enum JosekiError {
+    #[error("Could not read {path}: {source}")]
+    Io {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    #[error("Could not load {path}: {source}")]
+    Sgf { path: PathBuf, source: SgfError },
+    #[error("{0} Contains No Game Tree")]
+    Empty(PathBuf),
+}
+
+/// One of the four corners of the board.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Hash)]
+pub enum Corner {
+    BottomLeft,
+    BottomRight,
+    TopLeft,
+    TopRight,
+}
+
+impl Corner {
+    pub const ALL: [Corner; 4] = [
+        Corner::BottomLeft,
+        Corner::BottomRight,
+        Corner::TopLeft,
+        Corner::TopRight,
+    ];
+
+    /// The corner whose quarter of the board holds the point. Points on a middle line count
+    /// for the lower or left corner.
+    pub fn of_point((r, c): (usize, usize), size: usize) -> Corner {
+        let half = size / 2;
+        match (r > half, c > half) {
+            (false, false) => Corner::BottomLeft,
+            (false, true) => Corner::BottomRight,
+            (true, false) => Corner::TopLeft,
+            (true, true) => Corner::TopRight,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Corner::BottomLeft => "Bottom left",
+            Corner::BottomRight => "Bottom right",
+            Corner::TopLeft => "Top left",
+            Corner::TopRight => "Top right",
+        }
+    }
+}
+
+/// A known move in a joseki position.
+#[derive(Clone, Debug, PartialEq)]
+pub struct JosekiContinuation {
+    /// The move on the board, `Pass` for playing elsewhere.
+    pub mv: BadukMove,
+    pub player: Player,
+    /// The first line of the dictionary's comment on the move.
+    pub label: Option<String>,
+    pub evaluation: Option<MoveEvaluation>,
+    /// How many lines of the dictionary go on from the move.
+    pub variations: usize,
+}
+
+// A continuation as stored, in the frame where the joseki corner is the bottom left one
+#[derive(Clone, Debug)]
+struct StoredContinuation {
+    point: Option<(usize, usize)>,
+    player: Player,
+    label: Option<String>,
+    evaluation: Option<MoveEvaluation>,
+    variations: usize,
+}
+
+/// Corner sequences and their continuations, built from the variations of an SGF game tree such
+/// as a joseki dictionary.
+///
+/// A position is looked up by the stones in one corner region only, so a joseki is found in
+/// every corner, whichever way the board is turned and whichever colour started it.
+pub struct JosekiDictionary<const SIZE: usize> {
+    positions: HashMap<u64, Vec<StoredContinuation>>,
+}
+
+impl<const SIZE: usize> Default for JosekiDictionary<SIZE> {
+    fn default() -> Self {
+        JosekiDictionary {
+            positions: HashMap::new(),
+        }
+    }
+}
+
+impl<const SIZE: usize> JosekiDictionary<SIZE> {
+    /// The rows and columns from the edge that make up a corner region, just over half the
+    /// board.
+    pub const REGION: usize = SIZE / 2 + 1;
+
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The number of corner positions in the dictionary.
+    pub fn len(&self) -> usize {
+        self.positions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.positions.is_empty()
+    }
+
+    /// Loads the first game tree of an SGF file with every variation.
+    pub fn load(path: &Path) -> Result<Self, JosekiError> {
+        let contents = std::fs::read_to_string(path).map_err(|source| JosekiError::Io {
+            path: path.to_path_buf(),
+            source,
+        })?;
+        let sgf_error = |source| JosekiError::Sgf {
+            path: path.to_path_buf(),
+            source,
+        };
+        let collection = sgf::parse(&contents).map_err(sgf_error)?;
+        let root = collection
+            .first()
+            .ok_or_else(|| JosekiError::Empty(path.to_path_buf()))?;
+        let tree = sgf::to_game_tree::<SIZE>(root).map_err(sgf_error)?;
+        Ok(Self::from_game_tree(&tree))
+    }
+
+    /// Adds every position of the tree whose stones all lie in one corner region.
+    pub fn from_game_tree(tree: &GameTree<BadukClassical<SIZE>>) -> Self {
+        let mut dictionary = Self::new();
+        dictionary.add_node(tree.root());
+        dictionary
+    }
+
+    /// Adds the node and the nodes below it, returning the number of lines ending below it.
+    fn add_node(&mut self, node: &GameNode<BadukClassical<SIZE>>) -> usize {
+        let variations: Vec<usize> = node
+            .children
+            .values()
+            .map(|child| self.add_node(child))
+            .collect();
+
+        // The orientations that move every stone into the bottom left corner region, the empty
+        // board fits all of them
+        let game = &node.game;
+        let stones: Vec<(usize, usize)> = (0..SIZE)
+            .flat_map(|r| (0..SIZE).map(move |c| (r, c)))
+            .filter(|&(r, c)| game.board.get_point(r, c) != Some(Point::Empty))
+            .collect();
+        let symmetries: Vec<Symmetry> = Symmetry::ALL
+            .into_iter()
+            .filter(|symmetry| {
+                stones
+                    .iter()
+                    .all(|point| Self::in_region(symmetry.apply(*point, SIZE)))
+            })
+            .collect();
+        let Some(&first_symmetry) = symmetries.first() else {
+            return variations.iter().sum::<usize>().max(1);
+        };
+
+        for ((mv, child), child_variations) in node.children.iter().zip(&variations) {
+            // A move in the region is stored in an orientation that keeps it there, anything
+            // else is playing elsewhere
+            let (symmetry, point) = match mv {
+                BadukMove::Play { coordinates } => symmetries
+                    .iter()
+                    .map(|symmetry| (*symmetry, symmetry.apply(*coordinates, SIZE)))
+                    .find(|(_, point)| Self::in_region(*point))
+                    .map_or((first_symmetry, None), |(symmetry, point)| {
+                        (symmetry, Some(point))
+                    }),
+                BadukMove::Pass | BadukMove::Resign => (first_symmetry, None),
+            };
+            let label = child
+                .annotations
+                .comment
+                .as_deref()
+                .and_then(|comment| comment.lines().next())
+                .map(|line| line.trim().to_string())
+                .filter(|line| !line.is_empty());
+
+            let continuations = self
+                .positions
+                .entry(Self::region_key(game, symmetry, false))
+                .or_default();
+            match continuations
+                .iter_mut()
+                .find(|known| known.point == point && known.player == game.turn)
+            {
+                Some(known) => {
+                    known.variations += child_variations;
+                    known.label = known.label.take().or(label);
+                }
+                None => continuations.push(StoredContinuation {
+                    point,
+                    player: game.turn,
+                    label,
+                    evaluation: child.annotations.move_evaluation,
+                    variations: *child_variations,
+                }),
+            }
+        }
+        variations.iter().sum::<usize>().max(1)
+    }
+
+    fn in_region((r, c): (usize, usize)) -> bool {
+        r < Self::REGION && c < Self::REGION
+    }
+
+    /// A hash of the stones in the bottom left region after applying the symmetry, with the
+    /// colours swapped if asked to, and of the player to move.
+    fn region_key(game: &BadukClassical<SIZE>, symmetry: Symmetry, swap_colours: bool) -> u64 {
+        let colour_index = |player: Player| match (player, swap_colours) {
+            (Player::Black, false) | (Player::White, true) => 0,
+            (Player::White, false) | (Player::Black, true) => 1,
+        };
+        let inverse = symmetry.inverse();
+        let mut key = zobrist_key(colour_index(game.turn) as u64);
+        for r in 0..Self::REGION {
+            for c in 0..Self::REGION {
+                let (board_r, board_c) = inverse.apply((r, c), SIZE);
+                if let Some(Point::Stone(player)) = game.board.get_point(board_r, board_c) {
+                    key ^=
+                        zobrist_key(2 + ((r * Self::REGION + c) * 2 + colour_index(player)) as u64);
+                }
+            }
+        }
+        key
+    }
+
+    /// The known continuations in the corner, gathered from both orientations of the corner and
+    /// with the colours swapped. A move stored more than once is listed once with its variations
+    /// added up. Moves are given on the board as the game has it, most played first.
+    pub fn continuations(
+        &self,
+        game: &BadukClassical<SIZE>,
+        corner: Corner,
+    ) -> Vec<JosekiContinuation> {
+        let mut continuations: Vec<JosekiContinuation> = Vec::new();
+        // A corner that is symmetric along its diagonal has the same key in both orientations,
+        // its moves are only counted once
+        let mut seen_keys = HashSet::new();
+        for symmetry in Symmetry::ALL {
+            let bottom_left = symmetry.inverse().apply((0, 0), SIZE);
+            if Corner::of_point(bottom_left, SIZE) != corner {
+                continue;
+            }
+            for swap_colours in [false, true] {
+                let key = Self::region_key(game, symmetry, swap_colours);
+                if !seen_keys.insert(key) {
+                    continue;
+                }
+                let Some(stored) = self.positions.get(&key) else {
+                    continue;
+                };
+                for known in stored {
+                    let mv = known
+                        .point
+                        .map_or(BadukMove::Pass, |point| BadukMove::Play {
+                            coordinates: symmetry.inverse().apply(point, SIZE),
+                        });
+                    let player = if swap_colours {
+                        known.player.opponent()
+                    } else {
+                        known.player
+                    };
+                    match continuations
+                        .iter_mut()
+                        .find(|continuation| continuation.mv == mv && continuation.player == player)
+                    {
+                        Some(continuation) => {
+                            continuation.variations += known.variations;
+                            continuation.label = continuation.label.take().or(known.label.clone());
+                            continuation.evaluation = continuation.evaluation.or(known.evaluation);
+                        }
+                        None => continuations.push(JosekiContinuation {
+                            mv,
+                            player,
+                            label: known.label.clone(),
+                            evaluation: known.evaluation,
+                            variations: known.variations,
+                        }),
+                    }
+                }
+            }
+        }
+        continuations.sort_by_key(|continuation| std::cmp::Reverse(continuation.variations));
+        continuations
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::{Board, StatelessGame};
+
+    type Dictionary = JosekiDictionary<9>;
+
+    fn play(r: usize, c: usize) -> BadukMove {
+        BadukMove::Play {
+            coordinates: (r, c),
+        }
+    }
+
+    fn point((r, c): (usize, usize)) -> String {
+        sgf::format_point(r, c, 9)
+    }
+
+    fn dictionary(record: &str) -> Dictionary {
+        let collection = sgf::parse(record).unwrap();
+        Dictionary::from_game_tree(&sgf::to_game_tree::<9>(&collection[0]).unwrap())
+    }
+
+    #[test]
+    fn joseki_are_found_in_every_orientation() {
+        let dictionary = dictionary(&format!(
+            "(;SZ[9];B[{}];W[{}]C[Approach])",
+            point((2, 3)),
+            point((3, 1))
+        ));
+
+        for symmetry in Symmetry::ALL {
+            let black = symmetry.apply((2, 3), 9);
+            let game = BadukClassical::<9>::new()
+                .generate_next_board(&play(black.0, black.1))
+                .unwrap();
+            let continuations = dictionary.continuations(&game, Corner::of_point(black, 9));
+            let (r, c) = symmetry.apply((3, 1), 9);
+            assert_eq!(
+                continuations,
+                vec![JosekiContinuation {
+                    mv: play(r, c),
+                    player: Player::White,
+                    label: Some("Approach".to_string()),
+                    evaluation: None,
+                    variations: 1,
+                }],
+                "{symmetry:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn joseki_are_found_with_the_colours_swapped() {
+        let dictionary = dictionary(&format!(
+            "(;SZ[9];B[{}];W[{}])",
+            point((2, 3)),
+            point((3, 1))
+        ));
+        let mut board = Board::<9>::default();
+        board.place_stone(2, 3, Player::White);
+        let game = BadukClassical::from_position(board, Player::Black);
+
+        let continuations = dictionary.continuations(&game, Corner::BottomLeft);
+        assert_eq!(continuations.len(), 1);
+        assert_eq!(continuations[0].mv, play(3, 1));
+        assert_eq!(continuations[0].player, Player::Black);
+    }
+
+    #[test]
+    fn continuations_stored_under_several_keys_are_merged() {
+        // The same joseki started once by Black and once, mirrored, by White, and a second answer
+        let dictionary = dictionary(&format!(
+            "(;SZ[9](;B[{b}](;W[{w}])(;W[{other}]))(;W[{mirrored_b}];B[{mirrored_w}]))",
+            b = point((2, 3)),
+            w = point((3, 1)),
+            other = point((2, 1)),
+            mirrored_b = point((3, 2)),
+            mirrored_w = point((1, 3)),
+        ));
+        let game = BadukClassical::<9>::new()
+            .generate_next_board(&play(2, 3))
+            .unwrap();
+
+        let continuations = dictionary.continuations(&game, Corner::BottomLeft);
+        let moves: Vec<(BadukMove, Player, usize)> = continuations
+            .iter()
+            .map(|continuation| {
+                (
+                    continuation.mv,
+                    continuation.player,
+                    continuation.variations,
+                )
+            })
+            .collect();
+        assert_eq!(
+            moves,
+            vec![
+                (play(3, 1), Player::White, 2),
+                (play(2, 1), Player::White, 1)
+            ]
+        );
+    }
+}
@@ -0,0 +1,20 @@
+pub mod bot;
+pub mod cli;
+pub mod clock;
+pub mod collection_stats;
+pub mod evaluation;
+pub mod game;
+pub mod joseki;
+pub mod local_patterns;
+pub mod mcts_bot;
+pub mod opening_book;
+pub mod pattern_database;
+pub mod playout;
+pub mod random_bot;
+pub mod rendering;
+pub mod search;
+pub mod sgf;
+pub mod solver;
+pub mod solver_bot;
+pub mod transposition;
+pub mod tsumego;
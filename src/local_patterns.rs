@@ -0,0 +1,629 @@
+use rand::Rng;
+use std::collections::{HashMap, HashSet};
+use std::fmt::{self, Write as _};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use thiserror::Error;
+
+use crate::game::{
+    zobrist_key, BadukClassical, BadukMove, Board, GameTree, Player, Point, StatelessGame, Symmetry,
+};
+use crate::playout::PatternPlayout;
+
+#[derive(Error, Debug)]
+pub enum PatternWeightsError {
+    #[error("Could not read {path}: {source}")]
+    Io {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    #[error("Invalid Pattern Weights Line {line}: \"{text}\"")]
+    InvalidLine { line: usize, text: String },
+}
+
+/// The neighbourhood of a point that a local pattern looks at.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Hash)]
+pub enum PatternShape {
+    /// The eight points around it.
+    Square3x3,
+    /// The points at most `radius` steps away along the lines.
+    Diamond(usize),
+}
+
+impl PatternShape {
+    /// The shapes every move is matched with, each one containing the one before.
+    pub const ALL: [PatternShape; 3] = [
+        PatternShape::Square3x3,
+        PatternShape::Diamond(2),
+        PatternShape::Diamond(3),
+    ];
+
+    fn radius(self) -> usize {
+        match self {
+            PatternShape::Square3x3 => 1,
+            PatternShape::Diamond(radius) => radius,
+        }
+    }
+
+    fn contains(self, dr: isize, dc: isize) -> bool {
+        match self {
+            PatternShape::Square3x3 => dr.abs().max(dc.abs()) <= 1,
+            PatternShape::Diamond(radius) => dr.unsigned_abs() + dc.unsigned_abs() <= radius,
+        }
+    }
+
+    // Keys of different shapes never meet, even for the same neighbourhood
+    fn key_base(self) -> u64 {
+        (self.radius() as u64 * 2 + u64::from(matches!(self, PatternShape::Square3x3))) << 24
+    }
+}
+
+/// A hash of the neighbourhood of the point with the symmetry applied, as seen by `player`: the
+/// points around it are told apart as empty, own stone, opponent stone or off the board, so a
+/// shape on the edge never matches one in the middle.
+pub fn pattern_hash<const SIZE: usize>(
+    board: &Board<SIZE>,
+    (r, c): (usize, usize),
+    player: Player,
+    shape: PatternShape,
+    symmetry: Symmetry,
+) -> u64 {
+    let radius = shape.radius();
+    let span = 2 * radius + 1;
+    let mut hash = zobrist_key(shape.key_base());
+    for dr in -(radius as isize)..=radius as isize {
+        for dc in -(radius as isize)..=radius as isize {
+            if (dr, dc) == (0, 0) || !shape.contains(dr, dc) {
+                continue;
+            }
+            let point = r
+                .checked_add_signed(dr)
+                .zip(c.checked_add_signed(dc))
+                .and_then(|(point_r, point_c)| board.get_point(point_r, point_c));
+            let state = match point {
+                Some(Point::Empty) => continue,
+                Some(Point::Stone(owner)) if owner == player => 1,
+                Some(Point::Stone(_)) => 2,
+                None => 3,
+            };
+            let cell = (
+                (dr + radius as isize) as usize,
+                (dc + radius as isize) as usize,
+            );
+            let (cell_r, cell_c) = symmetry.apply(cell, span);
+            hash ^= zobrist_key(shape.key_base() + ((cell_r * span + cell_c) * 4 + state) as u64);
+        }
+    }
+    hash
+}
+
+/// The hashes of the neighbourhood in all eight orientations, each given once.
+pub fn symmetric_pattern_hashes<const SIZE: usize>(
+    board: &Board<SIZE>,
+    point: (usize, usize),
+    player: Player,
+    shape: PatternShape,
+) -> Vec<u64> {
+    let mut hashes = Vec::with_capacity(Symmetry::ALL.len());
+    for symmetry in Symmetry::ALL {
+        let hash = pattern_hash(board, point, player, shape, symmetry);
+        if !hashes.contains(&hash) {
+            hashes.push(hash);
+        }
+    }
+    hashes
+}
+
+/// What a move does to the groups next to it.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Hash)]
+pub enum MoveFeature {
+    /// Takes an opponent group in atari.
+    Capture,
+    /// Gives an own group in atari at least two liberties.
+    EscapeAtari,
+    /// Leaves an opponent group with one liberty.
+    Atari,
+    /// Leaves the group of the new stone with one liberty without capturing.
+    SelfAtari,
+}
+
+impl MoveFeature {
+    pub const ALL: [MoveFeature; 4] = [
+        MoveFeature::Capture,
+        MoveFeature::EscapeAtari,
+        MoveFeature::Atari,
+        MoveFeature::SelfAtari,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            MoveFeature::Capture => "capture",
+            MoveFeature::EscapeAtari => "escape",
+            MoveFeature::Atari => "atari",
+            MoveFeature::SelfAtari => "self-atari",
+        }
+    }
+
+    fn index(self) -> usize {
+        self as usize
+    }
+}
+
+/// The features of `player` playing on the empty point.
+pub fn move_features<const SIZE: usize>(
+    board: &Board<SIZE>,
+    (r, c): (usize, usize),
+    player: Player,
+) -> Vec<MoveFeature> {
+    let adjacent = board.get_adjacent_points(r, c);
+    if adjacent
+        .iter()
+        .all(|&(adj_r, adj_c)| board.get_point(adj_r, adj_c) == Some(Point::Empty))
+    {
+        return Vec::new();
+    }
+
+    let mut captures = false;
+    let mut own_in_atari = false;
+    for &(adj_r, adj_c) in &adjacent {
+        if let Some(Point::Stone(owner)) = board.get_point(adj_r, adj_c) {
+            let liberties = board.count_liberties(&board.get_group(adj_r, adj_c));
+            captures |= owner != player && liberties == 1;
+            own_in_atari |= owner == player && liberties == 1;
+        }
+    }
+    let mut after = board.clone();
+    after.place_stone(r, c, player);
+    for &(adj_r, adj_c) in &adjacent {
+        if after.get_point(adj_r, adj_c) == Some(Point::Stone(player.opponent())) {
+            let group = after.get_group(adj_r, adj_c);
+            if after.count_liberties(&group) == 0 {
+                for &(group_r, group_c) in &group {
+                    after.remove_stone(group_r, group_c);
+                }
+            }
+        }
+    }
+    let ataris = adjacent.iter().any(|&(adj_r, adj_c)| {
+        after.get_point(adj_r, adj_c) == Some(Point::Stone(player.opponent()))
+            && after.count_liberties(&after.get_group(adj_r, adj_c)) == 1
+    });
+    let liberties = after.count_liberties(&after.get_group(r, c));
+
+    let mut features = Vec::new();
+    if captures {
+        features.push(MoveFeature::Capture);
+    }
+    if own_in_atari && liberties >= 2 {
+        features.push(MoveFeature::EscapeAtari);
+    }
+    if ataris {
+        features.push(MoveFeature::Atari);
+    }
+    if liberties == 1 && !captures {
+        features.push(MoveFeature::SelfAtari);
+    }
+    features
+}
+
+// A candidate move as the weights see it, the hashes of each shape in every orientation
+struct Candidate {
+    point: (usize, usize),
+    shapes: Vec<Vec<u64>>,
+    features: Vec<MoveFeature>,
+}
+
+/// Learnable weights for local patterns and move features, and the playout policy that samples
+/// moves by them.
+///
+/// Weights are kept as logarithms: a move's weight is `exp` of the sum of the weights of its
+/// patterns of every shape and of its features, so an unknown pattern leaves it unchanged. A
+/// pattern is stored under its hash in every orientation, so looking it up takes one hash.
+///
+/// The weights are saved as text, one feature or pattern per line:
+///
+/// ```text
+/// feature capture 2
+/// feature self-atari -2
+/// pattern 3f2a9c01d4e5b678 0.4315
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct PatternWeights {
+    patterns: HashMap<u64, f32>,
+    features: [f32; MoveFeature::ALL.len()],
+}
+
+/// Hand set feature weights and no patterns, a sensible policy before any learning.
+impl Default for PatternWeights {
+    fn default() -> Self {
+        PatternWeights {
+            patterns: HashMap::new(),
+            features: [2.0, 1.5, 0.5, -2.0],
+        }
+    }
+}
+
+impl PatternWeights {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The number of known pattern hashes, counting each orientation.
+    pub fn len(&self) -> usize {
+        self.patterns.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.patterns.is_empty()
+    }
+
+    pub fn feature_weight(&self, feature: MoveFeature) -> f32 {
+        self.features[feature.index()]
+    }
+
+    pub fn set_feature_weight(&mut self, feature: MoveFeature, weight: f32) {
+        self.features[feature.index()] = weight;
+    }
+
+    /// The logarithm of the weight of `player` playing on the empty point.
+    pub fn score<const SIZE: usize>(
+        &self,
+        board: &Board<SIZE>,
+        point: (usize, usize),
+        player: Player,
+    ) -> f32 {
+        let patterns: f32 = PatternShape::ALL
+            .into_iter()
+            .filter_map(|shape| {
+                let hash = pattern_hash(board, point, player, shape, Symmetry::Identity);
+                self.patterns.get(&hash)
+            })
+            .sum();
+        let features: f32 = move_features(board, point, player)
+            .into_iter()
+            .map(|feature| self.feature_weight(feature))
+            .sum();
+        patterns + features
+    }
+
+    // The empty points the policy considers, all but the player's own eyes
+    fn candidate_points<const SIZE: usize>(game: &BadukClassical<SIZE>) -> Vec<(usize, usize)> {
+        (0..SIZE)
+            .flat_map(|r| (0..SIZE).map(move |c| (r, c)))
+            .filter(|&(r, c)| {
+                game.board.get_point(r, c) == Some(Point::Empty)
+                    && !game.board.is_eye(r, c, game.turn)
+            })
+            .collect()
+    }
+
+    /// The weight of every move the policy may play, legal or not, relative to the best one so
+    /// that large scores don't overflow.
+    pub fn move_weights<const SIZE: usize>(
+        &self,
+        game: &BadukClassical<SIZE>,
+    ) -> Vec<(BadukMove, f32)> {
+        let scores: Vec<((usize, usize), f32)> = Self::candidate_points(game)
+            .into_iter()
+            .map(|coordinates| (coordinates, self.score(&game.board, coordinates, game.turn)))
+            .collect();
+        let max_score = scores
+            .iter()
+            .map(|(_, score)| *score)
+            .fold(f32::NEG_INFINITY, f32::max);
+        scores
+            .into_iter()
+            .map(|(coordinates, score)| {
+                (BadukMove::Play { coordinates }, (score - max_score).exp())
+            })
+            .collect()
+    }
+
+    /// Samples a move by weight, like the reasonable policy never filling its own eyes and only
+    /// passing once nothing else is left. Illegal moves are dropped as they are drawn.
+    pub fn choose_move<const SIZE: usize, R: Rng>(
+        &self,
+        game: &BadukClassical<SIZE>,
+        rng: &mut R,
+    ) -> BadukMove {
+        let mut moves = self.move_weights(game);
+        let mut total: f32 = moves.iter().map(|(_, weight)| weight).sum();
+        // Weights that are infinite or not numbers can't be sampled from, so every move is
+        // as likely as the others
+        if !total.is_finite() {
+            for (_, weight) in &mut moves {
+                *weight = 1.0;
+            }
+            total = moves.len() as f32;
+        }
+        while total > 0.0 && !moves.is_empty() {
+            let mut target = rng.gen_range(0.0..total);
+            let index = moves
+                .iter()
+                .position(|(_, weight)| {
+                    target -= weight;
+                    target < 0.0
+                })
+                .unwrap_or(moves.len() - 1);
+            let (mv, weight) = moves.swap_remove(index);
+            if game.is_legal(&mv) {
+                return mv;
+            }
+            total -= weight;
+        }
+        BadukMove::Pass
+    }
+
+    /// One step of gradient ascent on the likelihood of the played move among the candidates,
+    /// raising the weights of what it has and lowering those of the moves it was chosen over.
+    /// Patterns are only added for played moves, so the table grows with the games seen and not
+    /// with every neighbourhood on the board.
+    pub fn learn<const SIZE: usize>(
+        &mut self,
+        game: &BadukClassical<SIZE>,
+        played: BadukMove,
+        learning_rate: f32,
+    ) {
+        let BadukMove::Play {
+            coordinates: played,
+        } = played
+        else {
+            return;
+        };
+        let candidates: Vec<Candidate> = Self::candidate_points(game)
+            .into_iter()
+            .map(|point| Candidate {
+                point,
+                shapes: PatternShape::ALL
+                    .into_iter()
+                    .map(|shape| symmetric_pattern_hashes(&game.board, point, game.turn, shape))
+                    .collect(),
+                features: move_features(&game.board, point, game.turn),
+            })
+            .collect();
+        if !candidates.iter().any(|candidate| candidate.point == played) {
+            return;
+        }
+
+        let scores: Vec<f32> = candidates
+            .iter()
+            .map(|candidate| {
+                let patterns: f32 = candidate
+                    .shapes
+                    .iter()
+                    .filter_map(|hashes| self.patterns.get(&hashes[0]))
+                    .sum();
+                let features: f32 = candidate
+                    .features
+                    .iter()
+                    .map(|feature| self.feature_weight(*feature))
+                    .sum();
+                patterns + features
+            })
+            .collect();
+        let max_score = scores.iter().copied().fold(f32::NEG_INFINITY, f32::max);
+        let exponents: Vec<f32> = scores
+            .iter()
+            .map(|score| (score - max_score).exp())
+            .collect();
+        let total: f32 = exponents.iter().sum();
+
+        for (candidate, exponent) in candidates.iter().zip(exponents) {
+            let is_played = candidate.point == played;
+            let target = if is_played { 1.0 } else { 0.0 };
+            let step = learning_rate * (target - exponent / total);
+            for hashes in &candidate.shapes {
+                if is_played || self.patterns.contains_key(&hashes[0]) {
+                    for hash in hashes {
+                        *self.patterns.entry(*hash).or_insert(0.0) += step;
+                    }
+                }
+            }
+            for feature in &candidate.features {
+                self.features[feature.index()] += step;
+            }
+        }
+    }
+
+    /// Learns from every move of the game's main line.
+    pub fn learn_game<const SIZE: usize>(
+        &mut self,
+        tree: &GameTree<BadukClassical<SIZE>>,
+        learning_rate: f32,
+    ) {
+        let mut game = &tree.root().game;
+        for (mv, node) in tree.main_line() {
+            self.learn(game, *mv, learning_rate);
+            game = &node.game;
+        }
+    }
+
+    pub fn write(&self) -> String {
+        let mut text = String::new();
+        for feature in MoveFeature::ALL {
+            let _ = writeln!(
+                text,
+                "feature {} {}",
+                feature.name(),
+                self.feature_weight(feature)
+            );
+        }
+        let mut patterns: Vec<(&u64, &f32)> = self.patterns.iter().collect();
+        patterns.sort_by_key(|(hash, _)| **hash);
+        for (hash, weight) in patterns {
+            let _ = writeln!(text, "pattern {hash:016x} {weight}");
+        }
+        text
+    }
+
+    /// Reads weights written by `write`. Features that are not given keep their default weight.
+    pub fn parse(text: &str) -> Result<Self, PatternWeightsError> {
+        let mut weights = Self::default();
+        let mut seen_features = HashSet::new();
+        for (index, line) in text.lines().enumerate() {
+            let invalid = || PatternWeightsError::InvalidLine {
+                line: index + 1,
+                text: line.to_string(),
+            };
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            match fields.as_slice() {
+                [] => {}
+                ["feature", name, weight] => {
+                    let feature = MoveFeature::ALL
+                        .into_iter()
+                        .find(|feature| feature.name() == *name)
+                        .filter(|feature| seen_features.insert(*feature))
+                        .ok_or_else(invalid)?;
+                    let weight = weight.parse().map_err(|_| invalid())?;
+                    weights.set_feature_weight(feature, weight);
+                }
+                ["pattern", hash, weight] => {
+                    let hash = u64::from_str_radix(hash, 16).map_err(|_| invalid())?;
+                    let weight = weight.parse().map_err(|_| invalid())?;
+                    weights.patterns.insert(hash, weight);
+                }
+                _ => return Err(invalid()),
+            }
+        }
+        Ok(weights)
+    }
+
+    pub fn load(path: &Path) -> Result<Self, PatternWeightsError> {
+        let text = fs::read_to_string(path).map_err(|source| PatternWeightsError::Io {
+            path: path.to_path_buf(),
+            source,
+        })?;
+        Self::parse(&text)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), PatternWeightsError> {
+        fs::write(path, self.write()).map_err(|source| PatternWeightsError::Io {
+            path: path.to_path_buf(),
+            source,
+        })
+    }
+}
+
+/// The weights the `patterns` playout policy of a bot samples by, with the file they were read
+/// from. The default weights until a file is given.
+#[derive(Clone, Debug, Default)]
+pub struct PlayoutPatterns {
+    pub path: Option<PathBuf>,
+    pub weights: Arc<PatternWeights>,
+}
+
+impl PlayoutPatterns {
+    pub fn load(path: &Path) -> Result<Self, PatternWeightsError> {
+        Ok(PlayoutPatterns {
+            path: Some(path.to_path_buf()),
+            weights: Arc::new(PatternWeights::load(path)?),
+        })
+    }
+}
+
+/// The file name, empty for the default weights.
+impl fmt::Display for PlayoutPatterns {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.path {
+            Some(path) => write!(f, "{}", path.display()),
+            None => Ok(()),
+        }
+    }
+}
+
+impl<const SIZE: usize> PatternPlayout for BadukClassical<SIZE> {
+    fn pattern_move<R: Rng>(&self, weights: &PatternWeights, rng: &mut R) -> Option<BadukMove> {
+        Some(weights.choose_move(self, rng))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    fn play(r: usize, c: usize) -> BadukMove {
+        BadukMove::Play {
+            coordinates: (r, c),
+        }
+    }
+
+    // A black group in atari at the edge with stones of both colours around it
+    fn position() -> BadukClassical<9> {
+        [
+            play(0, 1),
+            play(0, 2),
+            play(1, 1),
+            play(1, 2),
+            play(3, 3),
+            play(0, 0),
+        ]
+        .iter()
+        .fold(BadukClassical::new(), |game, mv| {
+            game.generate_next_board(mv).unwrap()
+        })
+    }
+
+    #[test]
+    fn pattern_hashes_do_not_depend_on_the_orientation() {
+        let game = position();
+        for symmetry in Symmetry::ALL {
+            let board = game.board.transformed(symmetry);
+            for point in [(0, 0), (1, 0), (2, 2), (4, 4), (8, 8)] {
+                for shape in PatternShape::ALL {
+                    let mut hashes =
+                        symmetric_pattern_hashes(&game.board, point, Player::Black, shape);
+                    let mut transformed_hashes = symmetric_pattern_hashes(
+                        &board,
+                        symmetry.apply(point, 9),
+                        Player::Black,
+                        shape,
+                    );
+                    hashes.sort_unstable();
+                    transformed_hashes.sort_unstable();
+                    assert_eq!(hashes, transformed_hashes, "{symmetry:?} at {point:?}");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn learned_patterns_score_the_same_in_every_orientation() {
+        let game = position();
+        let mut weights = PatternWeights::new();
+        weights.learn(&game, play(2, 1), 0.5);
+        let score = weights.score(&game.board, (2, 1), game.turn);
+        assert!(score > 0.0);
+        for symmetry in Symmetry::ALL {
+            let transformed = weights.score(
+                &game.board.transformed(symmetry),
+                symmetry.apply((2, 1), 9),
+                game.turn,
+            );
+            assert!((transformed - score).abs() < 1e-6, "{symmetry:?}");
+        }
+    }
+
+    #[test]
+    fn extreme_weights_still_give_legal_moves() {
+        let game = position();
+        let mut rng = ChaCha8Rng::seed_from_u64(7);
+        for weight in [1e6, -1e6, f32::INFINITY, f32::NAN] {
+            let mut weights = PatternWeights::new();
+            weights.set_feature_weight(MoveFeature::Capture, weight);
+            weights.set_feature_weight(MoveFeature::Atari, -weight);
+            for _ in 0..20 {
+                let mv = weights.choose_move(&game, &mut rng);
+                assert!(game.is_legal(&mv), "{mv:?} with weight {weight}");
+            }
+        }
+        let mut weights = PatternWeights::new();
+        weights.set_feature_weight(MoveFeature::Capture, 1e6);
+        assert!(weights
+            .move_weights(&game)
+            .iter()
+            .all(|(_, weight)| weight.is_finite()));
+    }
+}
@@ -1,21 +1,276 @@
+use bevy::input::keyboard::{Key, KeyboardInput};
 use bevy::prelude::*;
+use bevy::tasks::{block_on, futures_lite::future, AsyncComputeTaskPool, Task};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
 
-mod game;
-mod random_bot;
-mod rendering;
+use badukrs::bot::{default_registry, parse_bot_spec, BotError, BotRegistry, DynGameBot};
+use badukrs::cli::{arg_value, has_flag, path_from_args};
+use badukrs::clock::{GameClock, ManualTime, TimeControl};
+use badukrs::game::{
+    BadukClassical, BadukMove, GameInfo, GameNode, GamePointer, GameResult, GameTree, MoveError,
+    Player, Point,
+};
+use badukrs::joseki::{Corner, JosekiDictionary};
+use badukrs::opening_book::{BookBot, OpeningBook};
+use badukrs::rendering::clock::{clock_color, clock_text, ClockText};
+use badukrs::rendering::game_info::{game_info_text, player_name, GameInfoField, GameInfoPanel};
+use badukrs::rendering::joseki_panel::{joseki_text, JosekiPanel};
+use badukrs::rendering::markup::{
+    annotation_text, draw_markup, spawn_markup_entities, MarkupEntity,
+};
+use badukrs::rendering::tree_panel::{rebuild_tree_panel, TreeNodeButton, TreePanel};
+use badukrs::rendering::{
+    board_to_world, setup, world_to_board, CommentText, MoveErrorText, StatusText, BOARD_SIZE,
+    CELL_SIZE,
+};
+use badukrs::search::SearchContext;
+use badukrs::sgf;
+use badukrs::tsumego::{AttemptOutcome, ProblemSet, ProblemStatus};
 
-use game::{BadukClassical, BadukMove, Player, Point, StatelessGame};
-use random_bot::RandomBot;
-use rendering::{setup, BOARD_SIZE, CELL_SIZE};
+/// Who makes the moves for one colour, a human or a bot picked from the registry by name.
+#[derive(Clone)]
+enum Seat {
+    Human,
+    Bot {
+        name: &'static str,
+        bot: Arc<dyn DynGameBot<BadukClassical<19>>>,
+    },
+}
+
+impl Seat {
+    fn is_human(&self) -> bool {
+        matches!(self, Seat::Human)
+    }
+
+    /// The next bot in the registry with its default settings, humans come after the last bot.
+    fn next(&self, registry: &BotRegistry<BadukClassical<19>>, book: Option<&Book>) -> Seat {
+        let entries = registry.entries();
+        let next_index = match self {
+            Seat::Human => 0,
+            Seat::Bot { name, .. } => entries
+                .iter()
+                .position(|entry| entry.name == *name)
+                .map_or(entries.len(), |index| index + 1),
+        };
+        entries
+            .get(next_index)
+            .and_then(|entry| {
+                let bot = entry.create(&Default::default()).ok()?;
+                Some(Seat::Bot {
+                    name: entry.name,
+                    bot: with_book(bot, book),
+                })
+            })
+            .unwrap_or(Seat::Human)
+    }
+
+    fn label(&self) -> &'static str {
+        match *self {
+            Seat::Human => "Human",
+            Seat::Bot { name, .. } => name,
+        }
+    }
+}
 
-use crate::random_bot::GameBot;
+type Book = Arc<OpeningBook<19>>;
+
+/// Lets the bot play from the opening book, if one was given with `--book`.
+fn with_book(
+    bot: Arc<dyn DynGameBot<BadukClassical<19>>>,
+    book: Option<&Book>,
+) -> Arc<dyn DynGameBot<BadukClassical<19>>> {
+    match book {
+        Some(book) => Arc::new(BookBot::new(bot, book.clone())),
+        None => bot,
+    }
+}
+
+const DEFAULT_SGF_PATH: &str = "game.sgf";
 
 #[derive(Resource)]
 struct GameState {
-    game: BadukClassical<19>,
-    white_bot: RandomBot<BadukClassical<19>>,
+    tree: GameTree<BadukClassical<19>>,
+    pointer: GamePointer<BadukClassical<19>>,
+    // Where S saves the game record, the file given with `--open` or game.sgf
+    sgf_path: PathBuf,
+    black: Seat,
+    white: Seat,
+    bots: BotRegistry<BadukClassical<19>>,
+    book: Option<Book>,
+    // Runs for the player to move in timed games, driven by the frame time
+    clock: Option<GameClock<ManualTime>>,
 }
 
+impl GameState {
+    fn seat(&self, player: Player) -> &Seat {
+        match player {
+            Player::Black => &self.black,
+            Player::White => &self.white,
+        }
+    }
+
+    fn current(&self) -> &GameNode<BadukClassical<19>> {
+        self.pointer
+            .node(&self.tree)
+            .expect("The game pointer always points into the game tree")
+    }
+
+    fn game(&self) -> &BadukClassical<19> {
+        &self.current().game
+    }
+
+    fn out_of_time(&self) -> bool {
+        self.clock
+            .as_ref()
+            .is_some_and(|clock| clock.flagged().is_some())
+    }
+
+    fn is_over(&self) -> bool {
+        self.game().is_game_over() || self.out_of_time()
+    }
+
+    /// How the game shown ended, including a loss on time.
+    fn result(&self) -> Option<GameResult> {
+        self.game()
+            .result(self.tree.info.komi)
+            .or_else(|| self.clock.as_ref().and_then(GameClock::result))
+    }
+
+    /// Checks whether `player` may play `mv` now, a player who ran out of time may not.
+    fn check_move(&self, player: Player, mv: &BadukMove) -> Result<(), MoveError> {
+        if self.out_of_time() {
+            return Err(MoveError::GameOver);
+        }
+        self.game().check_move(player, mv)
+    }
+
+    /// Plays a move from the current node, following an existing variation or starting a new one.
+    /// The result is recorded once the game ends.
+    fn play(&mut self, mv: BadukMove) -> Result<(), MoveError> {
+        let player = self.game().turn;
+        self.pointer.make_move(&mut self.tree, mv)?;
+        if self.tree.info.result.is_none() {
+            self.tree.info.result = self.game().result(self.tree.info.komi);
+        }
+
+        let game_over = self.game().is_game_over();
+        if let Some(clock) = self.clock.as_mut() {
+            if game_over {
+                clock.pause();
+            } else {
+                clock.end_move(player);
+            }
+        }
+        Ok(())
+    }
+
+    /// Sets up the clocks for the time control in the game information and starts the clock of
+    /// the player to move. Untimed and finished games have no clocks.
+    fn start_clock(&mut self) {
+        self.clock = None;
+        if self.tree.info.result.is_some() {
+            return;
+        }
+        match TimeControl::from_game_info(&self.tree.info) {
+            Ok(Some(control)) => {
+                let mut clock = GameClock::new(control, ManualTime::default());
+                clock.start(self.game().turn);
+                self.clock = Some(clock);
+            }
+            Ok(None) => {}
+            Err(err) => warn!("Playing without clocks: {err}"),
+        }
+    }
+
+    /// Starts over from `game`, keeping the players and settings of the game information.
+    fn reset(&mut self, game: BadukClassical<19>) {
+        let info = GameInfo {
+            result: None,
+            ..self.tree.info.clone()
+        };
+        self.tree = GameTree::new(game);
+        self.tree.info = info;
+        self.pointer = GamePointer::new();
+        self.start_clock();
+    }
+}
+
+/// The game information being edited, one text per `GameInfoField`. Nothing is being edited while
+/// it is empty.
+#[derive(Resource, Default)]
+struct GameInfoEditor {
+    values: Option<Vec<String>>,
+    selected: usize,
+}
+
+fn game_info_editor_closed(editor: Res<GameInfoEditor>) -> bool {
+    editor.values.is_none()
+}
+
+/// The joseki dictionary given with `--joseki` and whether its panel is shown.
+#[derive(Resource, Default)]
+struct JosekiExplorer {
+    dictionary: Option<JosekiDictionary<19>>,
+    visible: bool,
+}
+
+const MOVE_ERROR_SECONDS: f32 = 2.5;
+
+/// Why the last move was refused, shown until the timer runs out.
+#[derive(Resource, Default)]
+struct MoveFeedback(Option<(String, Timer)>);
+
+impl MoveFeedback {
+    fn show(&mut self, err: &MoveError) {
+        self.0 = Some((
+            err.to_string(),
+            Timer::from_seconds(MOVE_ERROR_SECONDS, TimerMode::Once),
+        ));
+    }
+}
+
+/// The bot move being searched for in the background, and a bot pondering while a human thinks.
+/// Dropping a task does not interrupt a search that is already running, the stop flags do.
+#[derive(Resource, Default)]
+struct BotThinking {
+    task: Option<Task<Result<BadukMove, MoveError>>>,
+    stop: Arc<AtomicBool>,
+    pondering: Option<Task<()>>,
+    ponder_stop: Arc<AtomicBool>,
+}
+
+impl BotThinking {
+    fn cancel(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        self.task = None;
+        self.stop_pondering();
+    }
+
+    fn stop_pondering(&mut self) {
+        self.ponder_stop.store(true, Ordering::Relaxed);
+        self.pondering = None;
+    }
+}
+
+#[derive(States, Clone, Copy, PartialEq, Eq, Hash, Debug, Default)]
+enum AppMode {
+    #[default]
+    Play,
+    Tsumego,
+}
+
+#[derive(Resource)]
+struct TsumegoSession {
+    problems: ProblemSet<BOARD_SIZE>,
+    index: usize,
+    line: Vec<BadukMove>,
+    result: Option<ProblemStatus>,
+}
+
+// The stone entities are only ever despawned as a whole, the point is kept for debugging
+#[allow(dead_code)]
 #[derive(Component)]
 struct Stone {
     row: usize,
@@ -24,72 +279,776 @@ struct Stone {
 }
 
 fn main() {
-    App::new()
-        .add_plugins(DefaultPlugins.set(WindowPlugin {
-            primary_window: Some(Window {
-                title: "Baduk/Go Game".to_string(),
-                resolution: (800.0, 800.0).into(),
-                ..default()
-            }),
+    let problems = path_from_args("--tsumego").and_then(|directory| {
+        let problems = ProblemSet::load(&directory)
+            .inspect_err(|err| eprintln!("Could not load problems: {err}"))
+            .ok()?;
+        for skipped in &problems.skipped {
+            eprintln!("Skipped a problem: {skipped}");
+        }
+        Some(problems)
+    });
+
+    let opened = path_from_args("--open");
+    let mut tree = opened
+        .as_deref()
+        .and_then(|path| {
+            load_game_record(path)
+                .inspect_err(|err| eprintln!("Could not open {}: {err}", path.display()))
+                .ok()
+        })
+        .unwrap_or_else(|| GameTree::new(BadukClassical::new()));
+    if let Some(main_time) = arg_value("--time").and_then(|time| time.parse().ok()) {
+        tree.info.main_time = Some(main_time);
+    }
+    if let Some(overtime) = arg_value("--overtime") {
+        tree.info.overtime = Some(overtime);
+    }
+
+    let bots = default_registry();
+    if has_flag("--list-bots") {
+        for entry in bots.entries() {
+            let options = entry
+                .default_options()
+                .iter()
+                .map(|(name, value)| format!("{name}={value}"))
+                .collect::<Vec<_>>()
+                .join(",");
+            println!("{}: {} [{options}]", entry.name, entry.description);
+        }
+        return;
+    }
+    let joseki = JosekiExplorer {
+        dictionary: path_from_args("--joseki").and_then(|path| {
+            JosekiDictionary::load(&path)
+                .inspect_err(|err| eprintln!("Could not load the joseki dictionary: {err}"))
+                .ok()
+        }),
+        visible: false,
+    };
+    let book = path_from_args("--book").and_then(|path| {
+        OpeningBook::load(&path)
+            .inspect_err(|err| eprintln!("Could not load the opening book: {err}"))
+            .ok()
+            .map(Arc::new)
+    });
+    let black = seat_from_args("--black", &bots, book.as_ref()).unwrap_or(Seat::Human);
+    let white = seat_from_args("--white", &bots, book.as_ref())
+        .unwrap_or_else(|| Seat::Human.next(&bots, book.as_ref()));
+
+    let mut game_state = GameState {
+        tree,
+        pointer: GamePointer::new(),
+        sgf_path: opened.unwrap_or_else(|| PathBuf::from(DEFAULT_SGF_PATH)),
+        black,
+        white,
+        bots,
+        book,
+        clock: None,
+    };
+    if problems.is_none() {
+        game_state.start_clock();
+    }
+
+    let mut app = App::new();
+    app.add_plugins(DefaultPlugins.set(WindowPlugin {
+        primary_window: Some(Window {
+            title: "Baduk/Go Game".to_string(),
+            resolution: (1100.0, 800.0).into(),
             ..default()
-        }))
-        .insert_resource(GameState {
-            game: BadukClassical::new(),
-            white_bot: RandomBot::new(),
+        }),
+        ..default()
+    }))
+    .insert_resource(game_state)
+    .insert_resource(joseki)
+    .init_resource::<BotThinking>()
+    .init_resource::<GameInfoEditor>()
+    .init_resource::<MoveFeedback>()
+    .add_systems(Startup, setup)
+    .add_systems(OnEnter(AppMode::Tsumego), start_tsumego_problem)
+    .add_systems(
+        Update,
+        (
+            (
+                tick_clock,
+                handle_game_info_editor,
+                (
+                    handle_input,
+                    handle_setup_keys,
+                    handle_navigation_keys,
+                    handle_tree_panel_clicks,
+                    handle_bot_turn,
+                    toggle_joseki_panel,
+                )
+                    .chain()
+                    .run_if(game_info_editor_closed),
+                update_play_status,
+                update_clock_display,
+                update_game_info_panel,
+                update_joseki_panel,
+            )
+                .chain()
+                .run_if(in_state(AppMode::Play)),
+            (
+                handle_tsumego_input,
+                handle_tsumego_keys,
+                update_tsumego_status,
+            )
+                .chain()
+                .run_if(in_state(AppMode::Tsumego)),
+            update_board_display,
+            update_move_feedback,
+            update_annotation_display,
+            draw_board_markup,
+            update_tree_panel,
+        ),
+    );
+
+    match problems {
+        Some(problems) => {
+            app.insert_state(AppMode::Tsumego)
+                .insert_resource(TsumegoSession {
+                    problems,
+                    index: 0,
+                    line: Vec::new(),
+                    result: None,
+                });
+        }
+        None => {
+            app.insert_state(AppMode::Play);
+        }
+    }
+
+    app.run();
+}
+
+// `--open <file.sgf>` loads a game record, `--tsumego <directory>` starts problem mode,
+// `--time <seconds>` with `--overtime <e.g. "5x30 byo-yomi">` sets the time control and
+// `--black`/`--white <human or a bot spec such as "mcts:playouts=2000">` pick the players and
+// `--book <book file>` lets the bots play from an opening book. `--joseki <file.sgf>` loads a joseki
+// dictionary for the J panel.
+fn seat_from_args(
+    flag: &str,
+    bots: &BotRegistry<BadukClassical<19>>,
+    book: Option<&Book>,
+) -> Option<Seat> {
+    let spec = arg_value(flag)?;
+    if spec == "human" {
+        return Some(Seat::Human);
+    }
+    let seat = parse_bot_spec(&spec).and_then(|(name, options)| {
+        let entry = bots
+            .get(name)
+            .ok_or_else(|| BotError::UnknownBot(name.to_string()))?;
+        Ok(Seat::Bot {
+            name: entry.name,
+            bot: with_book(entry.create(&options)?, book),
         })
-        .add_systems(Startup, setup)
-        .add_systems(
-            Update,
-            (handle_input, update_board_display, handle_bot_turn),
-        )
-        .run();
+    });
+    seat.inspect_err(|err| eprintln!("Could not create the {flag} player: {err}"))
+        .ok()
+}
+
+fn load_game_record(
+    path: &Path,
+) -> Result<GameTree<BadukClassical<BOARD_SIZE>>, Box<dyn std::error::Error>> {
+    let contents = std::fs::read_to_string(path)?;
+    let collection = sgf::parse(&contents)?;
+    let root = collection.first().ok_or("The file contains no games")?;
+    Ok(sgf::to_game_tree(root)?)
+}
+
+fn save_game_record(game_state: &GameState) -> std::io::Result<()> {
+    let contents = sgf::write(&[sgf::from_game_tree(&game_state.tree)]);
+    std::fs::write(&game_state.sgf_path, contents)
+}
+
+fn clicked_point(
+    mouse_button_input: &ButtonInput<MouseButton>,
+    windows: &Query<&Window>,
+    camera_query: &Query<(&Camera, &GlobalTransform), With<Camera2d>>,
+) -> Option<(usize, usize)> {
+    if !mouse_button_input.just_pressed(MouseButton::Left) {
+        return None;
+    }
+
+    let window = windows.single();
+    let (camera, camera_transform) = camera_query.single();
+
+    window
+        .cursor_position()
+        .and_then(|cursor| camera.viewport_to_world_2d(camera_transform, cursor).ok())
+        .and_then(world_to_board)
 }
 
 fn handle_input(
     mut game_state: ResMut<GameState>,
+    mut feedback: ResMut<MoveFeedback>,
     mouse_button_input: Res<ButtonInput<MouseButton>>,
     windows: Query<&Window>,
     camera_query: Query<(&Camera, &GlobalTransform), With<Camera2d>>,
 ) {
-    if game_state.game.turn != Player::Black || game_state.game.is_game_over() {
+    let Some((row, col)) = clicked_point(&mouse_button_input, &windows, &camera_query) else {
+        return;
+    };
+
+    // A click while a bot is to play comes from the human playing the other colour
+    let turn = game_state.game().turn;
+    let player = if game_state.seat(turn).is_human() {
+        turn
+    } else {
+        turn.opponent()
+    };
+    if !game_state.seat(player).is_human() {
         return;
     }
 
-    if mouse_button_input.just_pressed(MouseButton::Left) {
-        let window = windows.single();
-        let (camera, camera_transform) = camera_query.single();
+    let move_attempt = BadukMove::Play {
+        coordinates: (row, col),
+    };
+    let result = game_state.check_move(player, &move_attempt);
+    if let Err(err) = result.and_then(|()| game_state.play(move_attempt)) {
+        feedback.show(&err);
+    }
+}
 
-        if let Some(world_position) = window
-            .cursor_position()
-            .and_then(|cursor| camera.viewport_to_world_2d(camera_transform, cursor).ok())
-        {
-            // Convert world position to board coordinates
-            let board_x = world_position.x + (BOARD_SIZE - 1) as f32 * CELL_SIZE / 2.0;
-            let board_y = world_position.y + (BOARD_SIZE - 1) as f32 * CELL_SIZE / 2.0;
+// B and W cycle who plays each colour, N starts a new game, P passes and R resigns for a human
+// player and S saves the game record
+fn handle_setup_keys(
+    mut game_state: ResMut<GameState>,
+    mut thinking: ResMut<BotThinking>,
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+) {
+    if keyboard_input.just_pressed(KeyCode::KeyB) {
+        game_state.black = game_state
+            .black
+            .next(&game_state.bots, game_state.book.as_ref());
+        thinking.cancel();
+    }
+    if keyboard_input.just_pressed(KeyCode::KeyW) {
+        game_state.white = game_state
+            .white
+            .next(&game_state.bots, game_state.book.as_ref());
+        thinking.cancel();
+    }
+    if keyboard_input.just_pressed(KeyCode::KeyN) {
+        game_state.reset(BadukClassical::new());
+        thinking.cancel();
+    }
+    let human_to_play = game_state.seat(game_state.game().turn).is_human() && !game_state.is_over();
+    if keyboard_input.just_pressed(KeyCode::KeyP) && human_to_play {
+        let _ = game_state.play(BadukMove::Pass);
+    }
+    if keyboard_input.just_pressed(KeyCode::KeyR) && human_to_play {
+        let _ = game_state.play(BadukMove::Resign);
+    }
+    if keyboard_input.just_pressed(KeyCode::KeyS) {
+        match save_game_record(&game_state) {
+            Ok(()) => info!("Saved the game to {}", game_state.sgf_path.display()),
+            Err(err) => warn!("Could not save {}: {err}", game_state.sgf_path.display()),
+        }
+    }
+}
+
+// Left/Right step through the moves, Up/Down jump to the start and end of the line, M makes the
+// current variation the main line and Delete removes it
+fn handle_navigation_keys(
+    mut game_state: ResMut<GameState>,
+    mut thinking: ResMut<BotThinking>,
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+) {
+    let navigation_keys = [
+        KeyCode::ArrowLeft,
+        KeyCode::ArrowRight,
+        KeyCode::ArrowUp,
+        KeyCode::ArrowDown,
+        KeyCode::KeyM,
+        KeyCode::Delete,
+    ];
+    if !keyboard_input.any_just_pressed(navigation_keys) {
+        return;
+    }
 
-            let col = (board_x / CELL_SIZE).round() as usize;
-            let row = (board_y / CELL_SIZE).round() as usize;
+    let game_state = &mut *game_state;
+    let (tree, pointer) = (&mut game_state.tree, &mut game_state.pointer);
+    if keyboard_input.just_pressed(KeyCode::ArrowLeft) {
+        pointer.undo_move();
+    } else if keyboard_input.just_pressed(KeyCode::ArrowRight) {
+        let _ = pointer.redo(tree);
+    } else if keyboard_input.just_pressed(KeyCode::ArrowUp) {
+        pointer.go_to_start();
+    } else if keyboard_input.just_pressed(KeyCode::ArrowDown) {
+        pointer.go_to_end(tree);
+    } else if keyboard_input.just_pressed(KeyCode::KeyM) {
+        let _ = tree.promote_variation(pointer.moves());
+    } else if keyboard_input.just_pressed(KeyCode::Delete) {
+        let _ = pointer.delete_current(tree);
+    }
+    thinking.cancel();
+}
 
-            if row < BOARD_SIZE && col < BOARD_SIZE {
-                let move_attempt = BadukMove::Play {
-                    coordinates: (row, col),
-                };
-                if game_state.game.is_legal(&move_attempt) {
-                    let _ = game_state.game.make_move(move_attempt);
+// I opens the game information editor. While it is open typing edits the selected field, Up/Down
+// select another one, Enter applies the changes and Escape discards them.
+fn handle_game_info_editor(
+    mut editor: ResMut<GameInfoEditor>,
+    mut game_state: ResMut<GameState>,
+    mut keyboard_events: EventReader<KeyboardInput>,
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+) {
+    if editor.values.is_none() {
+        // The key presses belong to the other systems while the editor is closed
+        keyboard_events.clear();
+        if keyboard_input.just_pressed(KeyCode::KeyI) {
+            let info = &game_state.tree.info;
+            editor.values = Some(GameInfoField::ALL.map(|field| field.get(info)).to_vec());
+            editor.selected = 0;
+        }
+        return;
+    }
+    if keyboard_events.is_empty() {
+        return;
+    }
+
+    let editor = &mut *editor;
+    let Some(values) = editor.values.as_mut() else {
+        return;
+    };
+
+    for event in keyboard_events.read() {
+        if !event.state.is_pressed() {
+            continue;
+        }
+        match &event.logical_key {
+            Key::ArrowUp => {
+                editor.selected = (editor.selected + values.len() - 1) % values.len();
+            }
+            Key::ArrowDown | Key::Tab => editor.selected = (editor.selected + 1) % values.len(),
+            Key::Backspace => {
+                values[editor.selected].pop();
+            }
+            Key::Space => values[editor.selected].push(' '),
+            Key::Character(text) => values[editor.selected].push_str(text),
+            Key::Enter => {
+                let info = &mut game_state.tree.info;
+                for (field, value) in GameInfoField::ALL.iter().zip(values.iter()) {
+                    field.set(info, value);
                 }
+                editor.values = None;
+                return;
+            }
+            Key::Escape => {
+                editor.values = None;
+                return;
             }
+            _ => {}
         }
     }
 }
 
-fn handle_bot_turn(mut game_state: ResMut<GameState>) {
-    if game_state.game.turn == Player::White && !game_state.game.is_game_over() {
-        if let Ok(bot_move) = game_state.white_bot.select_move(&game_state.game) {
-            let _ = game_state.game.make_move(bot_move);
+fn update_game_info_panel(
+    editor: Res<GameInfoEditor>,
+    mut panel_query: Query<(&mut Text, &mut Visibility), With<GameInfoPanel>>,
+) {
+    if !editor.is_changed() {
+        return;
+    }
+
+    for (mut text, mut visibility) in &mut panel_query {
+        match &editor.values {
+            Some(values) => {
+                text.0 = game_info_text(values, editor.selected);
+                *visibility = Visibility::Visible;
+            }
+            None => *visibility = Visibility::Hidden,
         }
     }
 }
 
+// J shows or hides the joseki continuations, when a dictionary was loaded
+fn toggle_joseki_panel(
+    mut explorer: ResMut<JosekiExplorer>,
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+) {
+    if keyboard_input.just_pressed(KeyCode::KeyJ) && explorer.dictionary.is_some() {
+        explorer.visible = !explorer.visible;
+    }
+}
+
+/// Lists the known continuations in the corner of the last move.
+fn update_joseki_panel(
+    game_state: Res<GameState>,
+    explorer: Res<JosekiExplorer>,
+    mut panel_query: Query<(&mut Text, &mut Visibility), With<JosekiPanel>>,
+) {
+    if !game_state.is_changed() && !explorer.is_changed() {
+        return;
+    }
+
+    for (mut text, mut visibility) in &mut panel_query {
+        let Some(dictionary) = explorer.dictionary.as_ref().filter(|_| explorer.visible) else {
+            *visibility = Visibility::Hidden;
+            continue;
+        };
+        let corner = match game_state.pointer.moves().last() {
+            Some(BadukMove::Play { coordinates }) => {
+                Some(Corner::of_point(*coordinates, BOARD_SIZE))
+            }
+            _ => None,
+        };
+        let continuations = corner
+            .map(|corner| dictionary.continuations(game_state.game(), corner))
+            .unwrap_or_default();
+        text.0 = joseki_text(corner, &continuations);
+        *visibility = Visibility::Visible;
+    }
+}
+
+fn handle_tree_panel_clicks(
+    mut game_state: ResMut<GameState>,
+    mut thinking: ResMut<BotThinking>,
+    buttons: Query<(&Interaction, &TreeNodeButton), Changed<Interaction>>,
+) {
+    for (interaction, button) in &buttons {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+        let game_state = &mut *game_state;
+        if game_state
+            .pointer
+            .go_to(&mut game_state.tree, button.0.clone())
+            .is_ok()
+        {
+            thinking.cancel();
+        }
+    }
+}
+
+fn update_tree_panel(
+    mut commands: Commands,
+    game_state: Res<GameState>,
+    panels: Query<Entity, With<TreePanel>>,
+) {
+    if !game_state.is_changed() {
+        return;
+    }
+
+    for panel in &panels {
+        rebuild_tree_panel(&mut commands, panel, &game_state.tree, &game_state.pointer);
+    }
+}
+
+fn handle_bot_turn(mut game_state: ResMut<GameState>, mut thinking: ResMut<BotThinking>) {
+    if let Some(task) = thinking.bypass_change_detection().task.as_mut() {
+        let Some(bot_move) = block_on(future::poll_once(task)) else {
+            return;
+        };
+        thinking.task = None;
+        if let Ok(bot_move) = bot_move {
+            if game_state.play(bot_move).is_ok() {
+                start_pondering(&game_state, &mut thinking);
+            }
+        }
+        return;
+    }
+
+    // Bots only extend the end of a line, so earlier positions can be reviewed without them
+    // answering
+    if game_state.is_over() || !game_state.current().children.is_empty() {
+        return;
+    }
+
+    // Search on a copy of the position so the window keeps rendering while the bot thinks
+    let game = game_state.game().clone();
+    let context = SearchContext {
+        time_left: game_state
+            .clock
+            .as_ref()
+            .map(|clock| clock.remaining(game.turn)),
+        time_control: game_state.clock.as_ref().map(|clock| clock.control),
+        move_number: game_state.pointer.moves().len(),
+        board_size: BOARD_SIZE,
+        komi: game_state.tree.info.komi,
+        stop: Arc::default(),
+    };
+    let stop = context.stop.clone();
+    let Seat::Bot { bot, .. } = game_state.seat(game.turn) else {
+        return;
+    };
+    // The search can only start once pondering has let go of the bot's tree
+    thinking.stop_pondering();
+    let bot = bot.clone();
+    let task = AsyncComputeTaskPool::get().spawn(async move { bot.select_move(&game, &context) });
+    thinking.task = Some(task);
+    thinking.stop = stop;
+}
+
+/// Lets the bot that just moved keep searching while a human thinks about the reply.
+fn start_pondering(game_state: &GameState, thinking: &mut BotThinking) {
+    let game = game_state.game().clone();
+    if game_state.is_over() || !game_state.seat(game.turn).is_human() {
+        return;
+    }
+    let Seat::Bot { bot, .. } = game_state.seat(game.turn.opponent()) else {
+        return;
+    };
+    if !bot.supports_pondering() {
+        return;
+    }
+    let context = SearchContext {
+        move_number: game_state.pointer.moves().len(),
+        komi: game_state.tree.info.komi,
+        ..SearchContext::new(BOARD_SIZE)
+    };
+    thinking.ponder_stop = context.stop.clone();
+    let bot = bot.clone();
+    thinking.pondering =
+        Some(AsyncComputeTaskPool::get().spawn(async move { bot.ponder(&game, &context) }));
+}
+
+/// Runs the clock with the frame time and records a loss on time once a player's time runs out.
+fn tick_clock(
+    time: Res<Time>,
+    mut game_state: ResMut<GameState>,
+    mut thinking: ResMut<BotThinking>,
+) {
+    // The clock changes every frame, so only a loss on time counts as a change of the game
+    let Some(clock) = game_state.bypass_change_detection().clock.as_mut() else {
+        return;
+    };
+    clock.source_mut().advance(time.delta());
+    if clock.running().is_none() {
+        return;
+    }
+    let Some(result) = clock.result() else {
+        return;
+    };
+    clock.pause();
+    game_state.tree.info.result.get_or_insert(result);
+    thinking.cancel();
+}
+
+fn update_clock_display(
+    game_state: Res<GameState>,
+    mut clock_query: Query<(&mut Text2d, &mut TextColor, &mut Visibility, &ClockText)>,
+) {
+    for (mut text, mut color, mut visibility, ClockText(player)) in &mut clock_query {
+        let Some(clock) = game_state.clock.as_ref() else {
+            *visibility = Visibility::Hidden;
+            continue;
+        };
+        let remaining = clock.remaining(*player);
+        text.0 = clock_text(*player, &clock.control, &remaining);
+        color.0 = clock_color(&remaining, clock.running() == Some(*player));
+        *visibility = Visibility::Visible;
+    }
+}
+
+fn update_play_status(
+    game_state: Res<GameState>,
+    thinking: Res<BotThinking>,
+    mut status_query: Query<&mut Text, With<StatusText>>,
+) {
+    if !game_state.is_changed() && !thinking.is_changed() {
+        return;
+    }
+
+    let game = game_state.game();
+    let info = &game_state.tree.info;
+    let progress = if let Some(result) = game_state.result() {
+        let outcome = match result {
+            GameResult::Score { winner, margin } => {
+                let (black_score, white_score) = game.calculate_score(info.komi);
+                format!("{winner:?} wins by {margin} ({black_score} - {white_score})")
+            }
+            GameResult::Resignation { winner } => format!("{winner:?} wins by resignation"),
+            GameResult::Time { winner } => format!("{winner:?} wins on time"),
+            GameResult::Forfeit { winner } => format!("{winner:?} wins by forfeit"),
+            GameResult::Draw => "Draw".to_string(),
+            GameResult::Void => "No result".to_string(),
+        };
+        format!("Game over: {outcome}. N: new game")
+    } else if thinking.task.is_some() {
+        let seat = game_state.seat(game.turn);
+        format!(
+            "{:?} ({}) is thinking... N: new game",
+            game.turn,
+            seat.label()
+        )
+    } else {
+        format!(
+            "{:?} to play. P: pass, R: resign, N: new game, S: save, I: game info",
+            game.turn
+        )
+    };
+    let mut status = format!(
+        "{}: {} (B), {}: {} (W)\n{}",
+        player_name(&info.black_player, &info.black_rank, "Black"),
+        game_state.black.label(),
+        player_name(&info.white_player, &info.white_rank, "White"),
+        game_state.white.label(),
+        progress
+    );
+    let alternatives = game_state
+        .pointer
+        .siblings(&game_state.tree)
+        .map_or(0, |siblings| siblings.len());
+    if let Seat::Bot { bot, .. } = game_state.seat(game.turn.opponent()) {
+        if let Some(report) = bot.search_report() {
+            status.push_str(&format!("\nLast search: {report}"));
+        }
+    }
+    if alternatives > 0 {
+        status.push_str(&format!(
+            "\nMove {} has {alternatives} alternatives. M: make main line, Delete: remove",
+            game_state.pointer.moves().len()
+        ));
+    }
+
+    for mut text in &mut status_query {
+        text.0 = status.clone();
+    }
+}
+
+fn start_tsumego_problem(mut game_state: ResMut<GameState>, mut session: ResMut<TsumegoSession>) {
+    session.line.clear();
+    session.result = None;
+    if let Some(problem) = session.problems.problems.get(session.index) {
+        game_state.reset(problem.tree.root().game.clone());
+    }
+}
+
+fn handle_tsumego_input(
+    mut game_state: ResMut<GameState>,
+    mut session: ResMut<TsumegoSession>,
+    mut feedback: ResMut<MoveFeedback>,
+    mouse_button_input: Res<ButtonInput<MouseButton>>,
+    windows: Query<&Window>,
+    camera_query: Query<(&Camera, &GlobalTransform), With<Camera2d>>,
+) {
+    if session.result.is_some() {
+        return;
+    }
+    let Some((row, col)) = clicked_point(&mouse_button_input, &windows, &camera_query) else {
+        return;
+    };
+    let move_attempt = BadukMove::Play {
+        coordinates: (row, col),
+    };
+    let game = game_state.game();
+    if let Err(err) = game.check_move(game.turn, &move_attempt) {
+        feedback.show(&err);
+        return;
+    }
+
+    let session = &mut *session;
+    let Some(problem) = session.problems.problems.get(session.index) else {
+        return;
+    };
+
+    let played = session.line.len();
+    let outcome = problem.respond(&mut session.line, move_attempt);
+    if session.line.len() == played {
+        // Show the wrong move even though the problem has no answer to it
+        let _ = game_state.play(move_attempt);
+    }
+    for mv in &session.line[played..] {
+        let _ = game_state.play(*mv);
+    }
+
+    let result = match outcome {
+        AttemptOutcome::Continue { .. } => return,
+        AttemptOutcome::Solved => ProblemStatus::Solved,
+        AttemptOutcome::Failed => ProblemStatus::Failed,
+    };
+    session.result = Some(result);
+    if let Err(err) = session.problems.record(session.index, result) {
+        warn!("Could not save problem progress: {err}");
+    }
+}
+
+fn handle_tsumego_keys(
+    game_state: ResMut<GameState>,
+    mut session: ResMut<TsumegoSession>,
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+) {
+    let problem_count = session.problems.problems.len();
+    if problem_count == 0 {
+        return;
+    }
+
+    if keyboard_input.just_pressed(KeyCode::KeyN) {
+        session.index = (session.index + 1) % problem_count;
+    } else if keyboard_input.just_pressed(KeyCode::KeyP) {
+        session.index = (session.index + problem_count - 1) % problem_count;
+    } else if !keyboard_input.just_pressed(KeyCode::KeyR) {
+        return;
+    }
+
+    start_tsumego_problem(game_state, session);
+}
+
+fn update_tsumego_status(
+    session: Res<TsumegoSession>,
+    game_state: Res<GameState>,
+    mut status_query: Query<&mut Text, With<StatusText>>,
+) {
+    if !session.is_changed() {
+        return;
+    }
+
+    let problems = &session.problems;
+    let Some(problem) = problems.problems.get(session.index) else {
+        for mut text in &mut status_query {
+            text.0 = format!("No problems found in {}", problems.directory.display());
+        }
+        return;
+    };
+
+    let progress = match session.result {
+        Some(ProblemStatus::Solved) => "Correct! N: next problem".to_string(),
+        Some(_) => "Wrong. R: retry, N: next problem".to_string(),
+        None => format!("{:?} to play", game_state.game().turn),
+    };
+    let status = format!(
+        "{} ({}/{}, {} solved)\n{}",
+        problem.name,
+        session.index + 1,
+        problems.problems.len(),
+        problems.solved_count(),
+        progress
+    );
+
+    for mut text in &mut status_query {
+        text.0 = status.clone();
+    }
+}
+
+fn update_move_feedback(
+    time: Res<Time>,
+    mut feedback: ResMut<MoveFeedback>,
+    mut text_query: Query<&mut Text2d, With<MoveErrorText>>,
+) {
+    let expired = match feedback.bypass_change_detection().0.as_mut() {
+        Some((_, timer)) => timer.tick(time.delta()).finished(),
+        None => false,
+    };
+    if expired {
+        feedback.0 = None;
+    }
+    if !feedback.is_changed() {
+        return;
+    }
+
+    let message = feedback
+        .0
+        .as_ref()
+        .map(|(message, _)| message.clone())
+        .unwrap_or_default();
+    for mut text in &mut text_query {
+        text.0 = message.clone();
+    }
+}
+
 fn update_board_display(
     mut commands: Commands,
     mut meshes: ResMut<Assets<Mesh>>,
@@ -109,9 +1068,8 @@ fn update_board_display(
     // Add stones based on current game state
     for row in 0..BOARD_SIZE {
         for col in 0..BOARD_SIZE {
-            if let Some(Point::Stone(player)) = game_state.game.board.get_point(row, col) {
-                let x = (col as f32 - (BOARD_SIZE - 1) as f32 / 2.0) * CELL_SIZE;
-                let y = (row as f32 - (BOARD_SIZE - 1) as f32 / 2.0) * CELL_SIZE;
+            if let Some(Point::Stone(player)) = game_state.game().board.get_point(row, col) {
+                let Vec2 { x, y } = board_to_world((row, col));
 
                 let color = match player {
                     Player::Black => Color::BLACK,
@@ -128,3 +1086,29 @@ fn update_board_display(
         }
     }
 }
+
+fn update_annotation_display(
+    mut commands: Commands,
+    game_state: Res<GameState>,
+    markup_query: Query<Entity, With<MarkupEntity>>,
+    mut comment_query: Query<&mut Text, With<CommentText>>,
+) {
+    if !game_state.is_changed() {
+        return;
+    }
+
+    for entity in markup_query.iter() {
+        commands.entity(entity).despawn();
+    }
+
+    let node = game_state.current();
+    spawn_markup_entities(&mut commands, &node.game.board, &node.annotations.markup);
+    for mut text in &mut comment_query {
+        text.0 = annotation_text(&node.annotations);
+    }
+}
+
+fn draw_board_markup(mut gizmos: Gizmos, game_state: Res<GameState>) {
+    let node = game_state.current();
+    draw_markup(&mut gizmos, &node.game.board, &node.annotations.markup);
+}
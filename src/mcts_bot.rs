@@ -0,0 +1,681 @@
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard, RwLock, RwLockReadGuard, RwLockWriteGuard};
+use std::time::{Duration, Instant};
+
+use crate::bot::{
+    move_rng, parse_option, parse_patterns, parse_seed, BotConfig, BotError, GameBot,
+};
+use crate::evaluation::{
+    BatchConfig, EvaluationError, EvaluationQueue, EvaluatorKind, PlayoutEvaluator,
+};
+use crate::game::{MoveError, Player, StatelessGame};
+use crate::local_patterns::PlayoutPatterns;
+use crate::playout::{random_playout, PatternPlayout, PlayoutPolicy};
+use crate::search::{SearchContext, SearchReport};
+use crate::transposition::TranspositionTable;
+
+// Playouts between looking at the clock, the stop flag and whether the best move is decided
+const CHECK_INTERVAL: u32 = 16;
+
+#[derive(Clone)]
+pub struct MctsConfig<G: StatelessGame> {
+    /// The most playouts to run for one move, however much time is left.
+    pub max_playouts: u32,
+    /// How long to think in games without a clock.
+    pub untimed_budget: Duration,
+    /// How strongly the search tries moves with few playouts over the best ones so far.
+    pub exploration: f32,
+    /// Makes the playouts reproducible. The chosen move only repeats when the search is limited
+    /// by playouts rather than by time.
+    pub seed: Option<u64>,
+    /// How the playouts pick their moves.
+    pub policy: PlayoutPolicy,
+    /// The weights the `patterns` policy samples by.
+    pub patterns: PlayoutPatterns,
+    /// Whether to keep searching while the opponent thinks.
+    pub ponder: bool,
+    /// The threads searching the tree together, 0 for one per core. With more than one thread
+    /// the moves no longer repeat for the same seed.
+    pub threads: usize,
+    /// The positions the transposition table holds, 0 to search without one.
+    pub table_size: usize,
+    /// Judges new leaves instead of random playouts, e.g. a network batching the leaves of all
+    /// search threads. The moves it rates highest are tried first below the leaf. Set in code,
+    /// it is used over the one named by `evaluator_kind`.
+    pub evaluator: Option<Arc<EvaluationQueue<G>>>,
+    /// The evaluator the bot builds for its searches when none is set in code.
+    pub evaluator_kind: EvaluatorKind,
+    /// How the leaves of the search threads are batched for the evaluator the bot builds.
+    pub batch: BatchConfig,
+}
+
+impl<G: StatelessGame> Default for MctsConfig<G> {
+    fn default() -> Self {
+        MctsConfig {
+            max_playouts: 100_000,
+            untimed_budget: Duration::from_secs(3),
+            exploration: 1.4,
+            seed: None,
+            policy: PlayoutPolicy::default(),
+            patterns: PlayoutPatterns::default(),
+            ponder: true,
+            threads: 1,
+            table_size: 1 << 16,
+            evaluator: None,
+            evaluator_kind: EvaluatorKind::default(),
+            batch: BatchConfig::default(),
+        }
+    }
+}
+
+impl<G: StatelessGame> MctsConfig<G> {
+    pub fn thread_count(&self) -> usize {
+        match self.threads {
+            0 => std::thread::available_parallelism().map_or(1, |threads| threads.get()),
+            threads => threads,
+        }
+    }
+}
+
+impl<G: StatelessGame> BotConfig for MctsConfig<G> {
+    fn options(&self) -> Vec<(&'static str, String)> {
+        vec![
+            ("playouts", self.max_playouts.to_string()),
+            ("time", self.untimed_budget.as_secs_f32().to_string()),
+            ("exploration", self.exploration.to_string()),
+            (
+                "seed",
+                self.seed.map(|seed| seed.to_string()).unwrap_or_default(),
+            ),
+            ("policy", self.policy.to_string()),
+            ("patterns", self.patterns.to_string()),
+            ("ponder", self.ponder.to_string()),
+            ("threads", self.threads.to_string()),
+            ("table", self.table_size.to_string()),
+            ("evaluator", self.evaluator_kind.to_string()),
+            ("batch", self.batch.batch_size.to_string()),
+            ("wait_ms", self.batch.max_wait.as_millis().to_string()),
+        ]
+    }
+
+    fn set_option(&mut self, name: &str, value: &str) -> Result<(), BotError> {
+        match name {
+            "playouts" => self.max_playouts = parse_option(name, value)?,
+            "time" => {
+                let seconds: f32 = parse_option(name, value)?;
+                self.untimed_budget =
+                    Duration::try_from_secs_f32(seconds).map_err(|_| BotError::InvalidValue {
+                        name: name.to_string(),
+                        value: value.to_string(),
+                    })?;
+            }
+            "exploration" => self.exploration = parse_option(name, value)?,
+            "seed" => self.seed = parse_seed(name, value)?,
+            "policy" => self.policy = parse_option(name, value)?,
+            "patterns" => self.patterns = parse_patterns(name, value)?,
+            "ponder" => self.ponder = parse_option(name, value)?,
+            "threads" => self.threads = parse_option(name, value)?,
+            "table" => self.table_size = parse_option(name, value)?,
+            "evaluator" => self.evaluator_kind = parse_option(name, value)?,
+            "batch" => self.batch.batch_size = parse_option(name, value)?,
+            "wait_ms" => self.batch.max_wait = Duration::from_millis(parse_option(name, value)?),
+            _ => return Err(BotError::UnknownOption(name.to_string())),
+        }
+        Ok(())
+    }
+}
+
+/// Monte Carlo tree search with random playouts, choosing moves by the upper confidence bound.
+/// With an evaluator in the config the leaves are judged by it instead of being played out.
+///
+/// The tree of the last search is kept, so that the search for the next move can continue from
+/// the part of it that follows the opponent's reply. Pondering grows that tree while the opponent
+/// thinks.
+#[derive(Clone)]
+pub struct MctsBot<G: StatelessGame> {
+    config: MctsConfig<G>,
+    // Locked for as long as a search or pondering runs, so a search waits for pondering to stop
+    tree: Arc<Mutex<Option<SearchTree<G>>>>,
+    last_report: Arc<Mutex<Option<SearchReport>>>,
+    // Shared by every search and pondering of the bot
+    table: Option<Arc<TranspositionTable>>,
+    // The evaluator built from the options
+    evaluator: Arc<Mutex<Option<BuiltEvaluator<G>>>>,
+}
+
+// An evaluator queue with the komi and playout length its evaluator was built for
+struct BuiltEvaluator<G: StatelessGame> {
+    komi: f32,
+    max_playout_moves: usize,
+    queue: Arc<EvaluationQueue<G>>,
+}
+
+impl<G> MctsBot<G>
+where
+    G: PatternPlayout + Send + 'static,
+    G::Move: Send,
+{
+    /// The config with the evaluator named by the options filled in. The evaluator is kept
+    /// between searches and built again only when the komi or the playout length changes.
+    fn search_config(&self, komi: f32, max_playout_moves: usize) -> MctsConfig<G> {
+        let mut config = self.config.clone();
+        if config.evaluator.is_some() || config.evaluator_kind == EvaluatorKind::None {
+            return config;
+        }
+        let mut built = lock(&self.evaluator);
+        let queue = match &*built {
+            Some(evaluator)
+                if evaluator.komi == komi && evaluator.max_playout_moves == max_playout_moves =>
+            {
+                evaluator.queue.clone()
+            }
+            _ => {
+                let evaluator = PlayoutEvaluator::new(
+                    config.policy,
+                    config.patterns.weights.clone(),
+                    komi,
+                    max_playout_moves,
+                    move_rng(config.seed, 0),
+                );
+                let queue = Arc::new(EvaluationQueue::new(evaluator, config.batch.clone()));
+                *built = Some(BuiltEvaluator {
+                    komi,
+                    max_playout_moves,
+                    queue: queue.clone(),
+                });
+                queue
+            }
+        };
+        config.evaluator = Some(queue);
+        config
+    }
+}
+
+impl<G> GameBot for MctsBot<G>
+where
+    G: PatternPlayout + PartialEq + Send + Sync + 'static,
+    G::Move: Send + Sync,
+{
+    type Game = G;
+    type Config = MctsConfig<G>;
+    fn from_config(config: MctsConfig<G>) -> Self {
+        let table =
+            (config.table_size > 0).then(|| Arc::new(TranspositionTable::new(config.table_size)));
+        MctsBot {
+            config,
+            tree: Arc::default(),
+            last_report: Arc::default(),
+            table,
+            evaluator: Arc::default(),
+        }
+    }
+
+    fn config(&self) -> &MctsConfig<G> {
+        &self.config
+    }
+
+    /// Searches until the time budget for the move is used, the stop flag is set or the playouts
+    /// left could no longer change which move was searched the most.
+    fn select_move(&self, game: &G, context: &SearchContext) -> Result<G::Move, MoveError> {
+        let mut rng = move_rng(self.config.seed, context.move_number);
+        let legal_moves = game.list_all_legal_moves();
+        match legal_moves.as_slice() {
+            [] => return Err(MoveError::NoLegalMoves),
+            [only_move] => return Ok(*only_move),
+            _ => {}
+        }
+
+        let max_playout_moves = context.board_size * context.board_size;
+        let config = &self.search_config(context.komi, max_playout_moves);
+        let budget = context.time_budget().unwrap_or(config.untimed_budget);
+        let started = Instant::now();
+        let mut kept = lock(&self.tree);
+        let (tree, reused_visits) = SearchTree::reuse(kept.take(), game, &mut rng);
+        if let Some(table) = &self.table {
+            table.new_search();
+        }
+
+        let playouts = tree.search(
+            config,
+            context.komi,
+            max_playout_moves,
+            &mut rng,
+            self.table.as_deref(),
+            |playouts| {
+                let elapsed = started.elapsed();
+                if context.stop_requested() || elapsed >= budget {
+                    return true;
+                }
+                let rate = playouts as f32 / elapsed.as_secs_f32().max(f32::EPSILON);
+                let playouts_left = (rate * (budget - elapsed).as_secs_f32())
+                    .min(config.max_playouts.saturating_sub(playouts) as f32);
+                tree.best_move_decided(playouts_left)
+            },
+        );
+
+        let best = tree.best_move().unwrap_or(legal_moves[0]);
+        *lock(&self.last_report) = Some(SearchReport {
+            playouts,
+            reused_visits,
+            elapsed: started.elapsed(),
+        });
+        // Keep the part of the tree after the chosen move for pondering and the next search
+        *kept = tree
+            .game
+            .generate_next_board(&best)
+            .ok()
+            .and_then(|next| tree.subtree_for(&next));
+        Ok(best)
+    }
+
+    fn supports_pondering(&self) -> bool {
+        self.config.ponder
+    }
+
+    /// Grows the kept tree for the position the opponent is thinking about, until the stop flag
+    /// is set or the tree holds as many playouts as one search may run.
+    fn ponder(&self, game: &G, context: &SearchContext) {
+        if !self.config.ponder {
+            return;
+        }
+        let mut rng = move_rng(self.config.seed, context.move_number);
+        let max_playout_moves = context.board_size * context.board_size;
+        let mut kept = lock(&self.tree);
+        let (tree, _) = SearchTree::reuse(kept.take(), game, &mut rng);
+        let config = self.search_config(context.komi, max_playout_moves);
+        // Only what the check reads is captured, the bot itself is not shared with the threads
+        let max_playouts = self.config.max_playouts;
+        let root = &tree.root;
+        tree.search(
+            &config,
+            context.komi,
+            max_playout_moves,
+            &mut rng,
+            self.table.as_deref(),
+            |_| context.stop_requested() || root.visits() >= max_playouts,
+        );
+        *kept = Some(tree);
+    }
+
+    fn search_report(&self) -> Option<SearchReport> {
+        *lock(&self.last_report)
+    }
+}
+
+struct SearchNode<M> {
+    mv: Option<M>,
+    // The player who made the move leading here, the wins are counted for them
+    player: Player,
+    // The position hash, for looking the node up in the transposition table
+    hash: u64,
+    children: RwLock<Vec<Arc<SearchNode<M>>>>,
+    untried: Mutex<Vec<M>>,
+    visits: AtomicU32,
+    // Twice the wins, so that a drawn playout adds a whole number
+    half_wins: AtomicU32,
+    // Playouts running through this node right now, counted as losses until they finish so that
+    // other threads spread out over the tree
+    virtual_loss: AtomicU32,
+}
+
+impl<M> SearchNode<M> {
+    fn new<G>(mv: Option<M>, game: &G, rng: &mut impl Rng) -> Self
+    where
+        G: StatelessGame<Move = M>,
+    {
+        let mut untried = if game.is_game_over() {
+            Vec::new()
+        } else {
+            game.list_all_legal_moves()
+        };
+        untried.shuffle(rng);
+        SearchNode {
+            mv,
+            player: game.turn().opponent(),
+            hash: game.position_hash(),
+            children: RwLock::default(),
+            untried: Mutex::new(untried),
+            visits: AtomicU32::new(0),
+            half_wins: AtomicU32::new(0),
+            virtual_loss: AtomicU32::new(0),
+        }
+    }
+
+    fn visits(&self) -> u32 {
+        self.visits.load(Ordering::Relaxed)
+    }
+
+    /// The child with the highest upper confidence bound. A child whose position was searched
+    /// more through other move orders is judged by the transposition table instead of its own
+    /// playouts.
+    fn select_child(
+        &self,
+        exploration: f32,
+        table: Option<&TranspositionTable>,
+    ) -> Option<Arc<SearchNode<M>>> {
+        let parent_visits = self.visits() + self.virtual_loss.load(Ordering::Relaxed);
+        let log_visits = (parent_visits.max(1) as f32).ln();
+        let upper_bound = |child: &SearchNode<M>| {
+            let virtual_loss = child.virtual_loss.load(Ordering::Relaxed);
+            let visits = (child.visits() + virtual_loss).max(1) as f32;
+            let win_rate = match table.and_then(|table| table.get(child.hash)) {
+                Some(stats) if stats.visits > child.visits() => {
+                    stats.wins / (stats.visits + virtual_loss) as f32
+                }
+                _ => child.half_wins.load(Ordering::Relaxed) as f32 / 2.0 / visits,
+            };
+            win_rate + exploration * (log_visits / visits).sqrt()
+        };
+        read(&self.children)
+            .iter()
+            .max_by(|a, b| upper_bound(a).total_cmp(&upper_bound(b)))
+            .cloned()
+    }
+}
+
+/// The search tree below one position. Node statistics are atomic and every node locks its own
+/// children, so several threads can run playouts on the same tree.
+struct SearchTree<G: StatelessGame> {
+    game: G,
+    root: Arc<SearchNode<G::Move>>,
+}
+
+impl<G> SearchTree<G>
+where
+    G: PatternPlayout + Send + Sync + 'static,
+    G::Move: Send + Sync,
+{
+    fn new(game: G, rng: &mut impl Rng) -> Self {
+        let root = Arc::new(SearchNode::new(None, &game, rng));
+        SearchTree { game, root }
+    }
+
+    /// The most searched move.
+    fn best_move(&self) -> Option<G::Move> {
+        read(&self.root.children)
+            .iter()
+            .max_by_key(|child| child.visits())
+            .and_then(|child| child.mv)
+    }
+
+    /// Whether the most searched move stays ahead even if every remaining playout went to the
+    /// runner up.
+    fn best_move_decided(&self, playouts_left: f32) -> bool {
+        let mut visits: Vec<u32> = read(&self.root.children)
+            .iter()
+            .map(|child| child.visits())
+            .collect();
+        visits.sort_unstable_by(|a, b| b.cmp(a));
+        match visits.as_slice() {
+            [best, second, ..] => (best - second) as f32 > playouts_left,
+            _ => false,
+        }
+    }
+
+    /// Runs playouts on the configured number of threads until `should_stop`, which is asked
+    /// every few playouts with the number started so far, says so or `max_playouts` have run.
+    /// Returns the number of playouts run.
+    fn search(
+        &self,
+        config: &MctsConfig<G>,
+        komi: f32,
+        max_playout_moves: usize,
+        rng: &mut ChaCha8Rng,
+        table: Option<&TranspositionTable>,
+        should_stop: impl Fn(u32) -> bool + Sync,
+    ) -> u32 {
+        let started = AtomicU32::new(0);
+        let done = AtomicBool::new(false);
+        let worker = |rng: &mut ChaCha8Rng| {
+            while !done.load(Ordering::Relaxed) {
+                let playout = started.fetch_add(1, Ordering::Relaxed) + 1;
+                if playout > config.max_playouts {
+                    break;
+                }
+                self.run_playout(config, komi, max_playout_moves, table, rng);
+                if playout.is_multiple_of(CHECK_INTERVAL) && should_stop(playout) {
+                    done.store(true, Ordering::Relaxed);
+                }
+            }
+        };
+
+        let threads = config.thread_count();
+        if threads == 1 {
+            worker(rng);
+        } else {
+            let mut rngs: Vec<ChaCha8Rng> = (0..threads)
+                .map(|_| ChaCha8Rng::seed_from_u64(rng.gen()))
+                .collect();
+            let worker = &worker;
+            std::thread::scope(|scope| {
+                for thread_rng in &mut rngs {
+                    scope.spawn(move || worker(thread_rng));
+                }
+            });
+        }
+        started.load(Ordering::Relaxed).min(config.max_playouts)
+    }
+
+    /// Walks down the tree to a leaf, adds one new node, plays the game out at random or asks the
+    /// evaluator about it and counts the result on the way back up.
+    fn run_playout(
+        &self,
+        config: &MctsConfig<G>,
+        komi: f32,
+        max_playout_moves: usize,
+        table: Option<&TranspositionTable>,
+        rng: &mut impl Rng,
+    ) {
+        let mut state = self.game.clone();
+        let mut path = vec![self.root.clone()];
+        self.root.virtual_loss.fetch_add(1, Ordering::Relaxed);
+        loop {
+            let node = path.last().expect("The path starts at the root");
+            if !lock(&node.untried).is_empty() {
+                break;
+            }
+            let Some(child) = node.select_child(config.exploration, table) else {
+                break;
+            };
+            let mv = child.mv.expect("Every node but the root has a move");
+            let Ok(next) = state.generate_next_board(&mv) else {
+                break;
+            };
+            state = next;
+            child.virtual_loss.fetch_add(1, Ordering::Relaxed);
+            path.push(child);
+        }
+
+        let leaf = path.last().expect("The path starts at the root");
+        let untried_move = lock(&leaf.untried).pop();
+        if let Some(mv) = untried_move {
+            if let Ok(next) = state.generate_next_board(&mv) {
+                let child = Arc::new(SearchNode::new(Some(mv), &next, rng));
+                child.virtual_loss.fetch_add(1, Ordering::Relaxed);
+                write(&leaf.children).push(child.clone());
+                path.push(child);
+                state = next;
+            }
+        }
+
+        let leaf = path.last().expect("The path starts at the root");
+        let winner = match &config.evaluator {
+            Some(evaluator) if !state.is_game_over() => {
+                // A failed evaluation leaves the leaf to a random playout
+                evaluated_winner(evaluator, leaf, &state, rng).unwrap_or_else(|_| {
+                    random_playout(
+                        state,
+                        config.policy,
+                        &config.patterns.weights,
+                        komi,
+                        max_playout_moves,
+                        rng,
+                    )
+                })
+            }
+            _ => random_playout(
+                state,
+                config.policy,
+                &config.patterns.weights,
+                komi,
+                max_playout_moves,
+                rng,
+            ),
+        };
+        for node in &path {
+            let half_wins = match winner {
+                Some(player) if player == node.player => 2,
+                Some(_) => 0,
+                None => 1,
+            };
+            node.half_wins.fetch_add(half_wins, Ordering::Relaxed);
+            node.visits.fetch_add(1, Ordering::Relaxed);
+            node.virtual_loss.fetch_sub(1, Ordering::Relaxed);
+            if let Some(table) = table {
+                table.record(node.hash, half_wins as f32 / 2.0);
+            }
+        }
+    }
+
+    /// The kept tree if it searched `game` or one of the positions following it, otherwise a new
+    /// tree, with the number of playouts carried over.
+    fn reuse(kept: Option<SearchTree<G>>, game: &G, rng: &mut impl Rng) -> (SearchTree<G>, u32)
+    where
+        G: PartialEq,
+    {
+        let reused = kept.and_then(|tree| {
+            if tree.game == *game {
+                return Some(tree);
+            }
+            tree.subtree_for(game)
+        });
+        match reused {
+            Some(tree) => {
+                let visits = tree.root.visits();
+                (tree, visits)
+            }
+            None => (SearchTree::new(game.clone(), rng), 0),
+        }
+    }
+
+    /// The part of the tree below the root's child that leads to `game`.
+    fn subtree_for(self, game: &G) -> Option<SearchTree<G>>
+    where
+        G: PartialEq,
+    {
+        let root = read(&self.root.children)
+            .iter()
+            .find(|child| {
+                child
+                    .mv
+                    .and_then(|mv| self.game.generate_next_board(&mv).ok())
+                    .is_some_and(|next| next == *game)
+            })?
+            .clone();
+        Some(SearchTree {
+            game: game.clone(),
+            root,
+        })
+    }
+}
+
+// A panicking search thread leaves the statistics usable, so poisoned locks are used anyway
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(|err| err.into_inner())
+}
+
+fn read<T>(rw_lock: &RwLock<T>) -> RwLockReadGuard<'_, T> {
+    rw_lock.read().unwrap_or_else(|err| err.into_inner())
+}
+
+fn write<T>(rw_lock: &RwLock<T>) -> RwLockWriteGuard<'_, T> {
+    rw_lock.write().unwrap_or_else(|err| err.into_inner())
+}
+
+/// Asks the evaluator about the leaf's position and orders the leaf's untried moves by its
+/// priors, so that the likeliest move is expanded next. The winner is drawn with the evaluated
+/// chance so that the node statistics stay whole playouts.
+fn evaluated_winner<G>(
+    evaluator: &EvaluationQueue<G>,
+    leaf: &SearchNode<G::Move>,
+    state: &G,
+    rng: &mut impl Rng,
+) -> Result<Option<Player>, EvaluationError>
+where
+    G: StatelessGame + Send + 'static,
+    G::Move: Send,
+{
+    let evaluation = evaluator.evaluate(state)?;
+    let priors: HashMap<G::Move, f32> = evaluation.policy.into_iter().collect();
+    let prior = |mv: &G::Move| priors.get(mv).copied().unwrap_or(0.0);
+    // Moves are expanded from the end of the list
+    lock(&leaf.untried).sort_by(|a, b| prior(a).total_cmp(&prior(b)));
+
+    let to_move = state.turn();
+    if rng.gen::<f32>() < evaluation.value {
+        Ok(Some(to_move))
+    } else {
+        Ok(Some(to_move.opponent()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bot::default_registry;
+    use crate::game::BadukClassical;
+
+    type Game = BadukClassical<5>;
+
+    #[test]
+    fn evaluator_options_are_reachable_from_a_spec() {
+        let registry = default_registry::<Game>();
+        let bot = registry
+            .create_from_spec("mcts:evaluator=playouts,batch=4,wait_ms=1")
+            .unwrap();
+        let options = bot.options();
+        for (name, value) in [("evaluator", "playouts"), ("batch", "4"), ("wait_ms", "1")] {
+            assert!(options.contains(&(name, value.to_string())), "{name}");
+        }
+        assert!(matches!(
+            registry.create_from_spec("mcts:evaluator=network"),
+            Err(BotError::InvalidValue { .. })
+        ));
+    }
+
+    #[test]
+    fn leaves_are_evaluated_in_batches_when_asked_for() {
+        let mut config = MctsConfig::<Game>::default();
+        for (name, value) in [
+            ("evaluator", "playouts"),
+            ("batch", "4"),
+            ("wait_ms", "1"),
+            ("threads", "4"),
+            ("playouts", "16"),
+            ("time", "30"),
+            ("ponder", "false"),
+        ] {
+            config.set_option(name, value).unwrap();
+        }
+        let bot = MctsBot::from_config(config);
+        let game = Game::new();
+        let context = SearchContext::new(5);
+        let mv = bot.select_move(&game, &context).unwrap();
+        assert!(game.is_legal(&mv));
+
+        let stats = lock(&bot.evaluator).as_ref().unwrap().queue.stats();
+        assert!(stats.positions > 0);
+        assert!(stats.average_batch_size() <= 4.0);
+        // The same evaluator serves the next search with the same komi
+        let queue = lock(&bot.evaluator).as_ref().unwrap().queue.clone();
+        bot.select_move(&game, &context).unwrap();
+        assert!(Arc::ptr_eq(
+            &queue,
+            &lock(&bot.evaluator).as_ref().unwrap().queue
+        ));
+    }
+}
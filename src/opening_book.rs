@@ -0,0 +1,409 @@
+use indexmap::IndexMap;
+use rand::Rng;
+use std::collections::HashMap;
+use std::fmt::Write as _;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use thiserror::Error;
+
+use crate::bot::{move_rng, DynGameBot};
+use crate::game::{BadukClassical, BadukMove, GameTree, MoveError, StatelessGame};
+use crate::search::{SearchContext, SearchReport};
+use crate::sgf::{self, SgfError};
+
+#[derive(Error, Debug)]
+pub enum OpeningBookError {
+    #[error("Could not read {path}: {source}")]
+    Io {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    #[error("Could not load {path}: {source}")]
+    Sgf { path: PathBuf, source: SgfError },
+    #[error("Invalid Opening Book Line {line}: \"{text}\"")]
+    InvalidLine { line: usize, text: String },
+    #[error("Opening Book Board Size {found} Does Not Match {expected}")]
+    BoardSize { found: usize, expected: usize },
+    #[error("Resigning Cannot Be an Opening Book Move")]
+    Resign,
+}
+
+/// The moves played from positions of a game collection, with how often each was played.
+///
+/// Positions are stored under the key of their canonical orientation, see
+/// `BadukClassical::canonical_hash`, so a position is found however the board is rotated or
+/// mirrored.
+///
+/// The book is saved as text, a "size" and a "games" line followed by one line per position with
+/// its key in hex and its moves as SGF points with their weights:
+///
+/// ```text
+/// size 19
+/// games 120
+/// 3f0a6c2e9d1b4a57 pd:64 dd:41 pass:1
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct OpeningBook<const SIZE: usize> {
+    /// The number of games the book was built from.
+    pub games: u32,
+    positions: HashMap<u64, IndexMap<BadukMove, u32>>,
+}
+
+impl<const SIZE: usize> OpeningBook<SIZE> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The number of positions in the book.
+    pub fn len(&self) -> usize {
+        self.positions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.positions.is_empty()
+    }
+
+    /// Counts `mv` as played `weight` more times in the position.
+    pub fn add(
+        &mut self,
+        game: &BadukClassical<SIZE>,
+        mv: BadukMove,
+        weight: u32,
+    ) -> Result<(), OpeningBookError> {
+        if mv == BadukMove::Resign {
+            return Err(OpeningBookError::Resign);
+        }
+        let (key, symmetry) = game.canonical_hash();
+        *self
+            .positions
+            .entry(key)
+            .or_default()
+            .entry(mv.transformed(symmetry, SIZE))
+            .or_insert(0) += weight;
+        Ok(())
+    }
+
+    /// Adds the first `max_moves` moves of the game's main line, up to a resignation.
+    pub fn add_game(&mut self, tree: &GameTree<BadukClassical<SIZE>>, max_moves: usize) {
+        let mut game = &tree.root().game;
+        for (mv, node) in tree.main_line().take(max_moves) {
+            if self.add(game, *mv, 1).is_err() {
+                break;
+            }
+            game = &node.game;
+        }
+        self.games += 1;
+    }
+
+    /// Builds a book from the first `max_moves` moves of every game in the `.sgf` files of
+    /// `directory`. Games on other board sizes are skipped.
+    pub fn from_sgf_directory(
+        directory: &Path,
+        max_moves: usize,
+    ) -> Result<Self, OpeningBookError> {
+        let io_error = |path: &Path| {
+            let path = path.to_path_buf();
+            move |source| OpeningBookError::Io { path, source }
+        };
+
+        let paths = sgf::sgf_files_in(directory).map_err(io_error(directory))?;
+
+        let mut book = Self::new();
+        for path in paths {
+            let contents = fs::read_to_string(&path).map_err(io_error(&path))?;
+            let collection = sgf::parse(&contents).map_err(|source| OpeningBookError::Sgf {
+                path: path.clone(),
+                source,
+            })?;
+            for sgf_root in &collection {
+                match sgf::to_game_tree::<SIZE>(sgf_root) {
+                    Ok(tree) => book.add_game(&tree, max_moves),
+                    Err(SgfError::BoardSize { .. }) => continue,
+                    Err(source) => {
+                        return Err(OpeningBookError::Sgf {
+                            path: path.clone(),
+                            source,
+                        })
+                    }
+                }
+            }
+        }
+        Ok(book)
+    }
+
+    /// The legal book moves in the position as the game has it oriented, most played first.
+    pub fn moves(&self, game: &BadukClassical<SIZE>) -> Vec<(BadukMove, u32)> {
+        let (key, symmetry) = game.canonical_hash();
+        let Some(moves) = self.positions.get(&key) else {
+            return Vec::new();
+        };
+        let inverse = symmetry.inverse();
+        let mut moves: Vec<(BadukMove, u32)> = moves
+            .iter()
+            .map(|(mv, weight)| (mv.transformed(inverse, SIZE), *weight))
+            .filter(|(mv, _)| game.is_legal(mv))
+            .collect();
+        moves.sort_by_key(|&(_, weight)| std::cmp::Reverse(weight));
+        moves
+    }
+
+    /// A book move played at least `min_weight` times, picked at random in proportion to how
+    /// often each was played. `None` once the game has left the book.
+    pub fn choose_move(
+        &self,
+        game: &BadukClassical<SIZE>,
+        min_weight: u32,
+        rng: &mut impl Rng,
+    ) -> Option<BadukMove> {
+        let moves: Vec<(BadukMove, u32)> = self
+            .moves(game)
+            .into_iter()
+            .filter(|(_, weight)| *weight >= min_weight.max(1))
+            .collect();
+        let total: u32 = moves.iter().map(|(_, weight)| weight).sum();
+        if total == 0 {
+            return None;
+        }
+        let mut pick = rng.gen_range(0..total);
+        moves.into_iter().find_map(|(mv, weight)| {
+            if pick < weight {
+                Some(mv)
+            } else {
+                pick -= weight;
+                None
+            }
+        })
+    }
+
+    pub fn write(&self) -> String {
+        let mut text = format!("size {SIZE}\ngames {}\n", self.games);
+        let mut keys: Vec<&u64> = self.positions.keys().collect();
+        keys.sort();
+        for key in keys {
+            let _ = write!(text, "{key:016x}");
+            for (mv, weight) in &self.positions[key] {
+                let mv = match mv {
+                    BadukMove::Play {
+                        coordinates: (r, c),
+                    } => sgf::format_point(*r, *c, SIZE),
+                    BadukMove::Pass => "pass".to_string(),
+                    BadukMove::Resign => unreachable!("`add` keeps resignations out of the book"),
+                };
+                let _ = write!(text, " {mv}:{weight}");
+            }
+            text.push('\n');
+        }
+        text
+    }
+
+    pub fn parse(text: &str) -> Result<Self, OpeningBookError> {
+        let mut book = Self::new();
+        for (index, line) in text.lines().enumerate() {
+            let invalid = || OpeningBookError::InvalidLine {
+                line: index + 1,
+                text: line.to_string(),
+            };
+            let mut words = line.split_whitespace();
+            let Some(first) = words.next() else {
+                continue;
+            };
+            match first {
+                "size" => {
+                    let size: usize = words
+                        .next()
+                        .and_then(|size| size.parse().ok())
+                        .ok_or_else(invalid)?;
+                    if size != SIZE {
+                        return Err(OpeningBookError::BoardSize {
+                            found: size,
+                            expected: SIZE,
+                        });
+                    }
+                }
+                "games" => {
+                    book.games = words
+                        .next()
+                        .and_then(|games| games.parse().ok())
+                        .ok_or_else(invalid)?;
+                }
+                key => {
+                    let key = u64::from_str_radix(key, 16).map_err(|_| invalid())?;
+                    let moves = book.positions.entry(key).or_default();
+                    for word in words {
+                        let (mv, weight) = word.split_once(':').ok_or_else(invalid)?;
+                        let mv = match mv {
+                            "pass" => BadukMove::Pass,
+                            point => match sgf::parse_point(point, SIZE) {
+                                Ok(Some(coordinates)) => BadukMove::Play { coordinates },
+                                _ => return Err(invalid()),
+                            },
+                        };
+                        let weight: u32 = weight.parse().map_err(|_| invalid())?;
+                        *moves.entry(mv).or_insert(0) += weight;
+                    }
+                }
+            }
+        }
+        Ok(book)
+    }
+
+    pub fn load(path: &Path) -> Result<Self, OpeningBookError> {
+        let text = fs::read_to_string(path).map_err(|source| OpeningBookError::Io {
+            path: path.to_path_buf(),
+            source,
+        })?;
+        Self::parse(&text)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), OpeningBookError> {
+        fs::write(path, self.write()).map_err(|source| OpeningBookError::Io {
+            path: path.to_path_buf(),
+            source,
+        })
+    }
+}
+
+/// Plays from an opening book while the game is in it and lets another bot choose the moves
+/// after that.
+pub struct BookBot<const SIZE: usize> {
+    inner: Arc<dyn DynGameBot<BadukClassical<SIZE>>>,
+    book: Arc<OpeningBook<SIZE>>,
+    /// Makes the choice between book moves reproducible, see `move_rng`.
+    pub seed: Option<u64>,
+    /// Moves played fewer times than this in the book's games are left out.
+    pub min_weight: u32,
+}
+
+impl<const SIZE: usize> BookBot<SIZE> {
+    pub fn new(
+        inner: Arc<dyn DynGameBot<BadukClassical<SIZE>>>,
+        book: Arc<OpeningBook<SIZE>>,
+    ) -> Self {
+        BookBot {
+            inner,
+            book,
+            seed: None,
+            min_weight: 1,
+        }
+    }
+}
+
+impl<const SIZE: usize> DynGameBot<BadukClassical<SIZE>> for BookBot<SIZE> {
+    fn select_move(
+        &self,
+        game: &BadukClassical<SIZE>,
+        context: &SearchContext,
+    ) -> Result<BadukMove, MoveError> {
+        let mut rng = move_rng(self.seed, context.move_number);
+        match self.book.choose_move(game, self.min_weight, &mut rng) {
+            Some(mv) => Ok(mv),
+            None => self.inner.select_move(game, context),
+        }
+    }
+
+    fn options(&self) -> Vec<(&'static str, String)> {
+        self.inner.options()
+    }
+
+    fn supports_pondering(&self) -> bool {
+        self.inner.supports_pondering()
+    }
+
+    fn ponder(&self, game: &BadukClassical<SIZE>, context: &SearchContext) {
+        self.inner.ponder(game, context)
+    }
+
+    fn search_report(&self) -> Option<SearchReport> {
+        self.inner.search_report()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bot::default_registry;
+    use crate::game::Symmetry;
+
+    type Book = OpeningBook<9>;
+
+    fn play(r: usize, c: usize) -> BadukMove {
+        BadukMove::Play {
+            coordinates: (r, c),
+        }
+    }
+
+    // Black opens at 2-2 or 2-6 and White answers 2-2 at 6-4
+    fn small_book() -> Book {
+        let mut book = Book::new();
+        let empty = BadukClassical::new();
+        book.add(&empty, play(2, 2), 3).unwrap();
+        book.add(&empty, play(2, 6), 1).unwrap();
+        let after_black = empty.generate_next_board(&play(2, 2)).unwrap();
+        book.add(&after_black, play(6, 4), 2).unwrap();
+        book.add(&after_black, BadukMove::Pass, 1).unwrap();
+        book.games = 4;
+        book
+    }
+
+    #[test]
+    fn resignations_are_not_book_moves() {
+        let mut book = Book::new();
+        assert!(matches!(
+            book.add(&BadukClassical::new(), BadukMove::Resign, 1),
+            Err(OpeningBookError::Resign)
+        ));
+        assert!(book.is_empty());
+    }
+
+    #[test]
+    fn written_books_read_back_the_same() {
+        let book = small_book();
+        let text = book.write();
+        let read = Book::parse(&text).unwrap();
+
+        assert_eq!(read.games, 4);
+        assert_eq!(read.len(), book.len());
+        assert_eq!(read.write(), text);
+        let after_black = BadukClassical::new()
+            .generate_next_board(&play(2, 2))
+            .unwrap();
+        assert_eq!(
+            read.moves(&after_black),
+            vec![(play(6, 4), 2), (BadukMove::Pass, 1)]
+        );
+        assert!(matches!(
+            OpeningBook::<13>::parse(&text),
+            Err(OpeningBookError::BoardSize {
+                found: 9,
+                expected: 13
+            })
+        ));
+    }
+
+    #[test]
+    fn book_bot_answers_rotated_and_mirrored_positions() {
+        // 2-3 has no symmetry of its own, so the answer in every orientation is one point
+        let mut book = Book::new();
+        let after_black = BadukClassical::new()
+            .generate_next_board(&play(2, 3))
+            .unwrap();
+        book.add(&after_black, play(6, 4), 1).unwrap();
+        let inner = default_registry()
+            .create("random", &Default::default())
+            .unwrap();
+        let bot = BookBot::new(inner, Arc::new(book));
+
+        for symmetry in Symmetry::ALL {
+            let game = BadukClassical::new()
+                .generate_next_board(&play(2, 3).transformed(symmetry, 9))
+                .unwrap();
+            let context = SearchContext::new(9);
+            assert_eq!(
+                bot.select_move(&game, &context).unwrap(),
+                play(6, 4).transformed(symmetry, 9),
+                "{symmetry:?}"
+            );
+        }
+    }
+}
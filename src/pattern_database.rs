@@ -0,0 +1,705 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+
+use crate::game::{BadukClassical, BadukMove, GameInfo, GameTree, Player, Point, Symmetry};
+use crate::sgf::{self, SgfError};
+
+#[derive(Error, Debug)]
+pub enum PatternSearchError {
+    #[error("Could not read {path}: {source}")]
+    Io {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    #[error("Could not load {path}: {source}")]
+    Sgf { path: PathBuf, source: SgfError },
+    #[error("Invalid Pattern Character '{character}' On Line {line}")]
+    InvalidCharacter { line: usize, character: char },
+    #[error("Pattern Rows Have Different Lengths")]
+    RaggedRows,
+    #[error("Pattern Has No Stones")]
+    NoStones,
+    #[error("Pattern Does Not Fit On The Board")]
+    TooLarge,
+}
+
+/// What a pattern asks for at one point.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Hash)]
+pub enum PatternPoint {
+    Black,
+    White,
+    Empty,
+    Any,
+}
+
+/// A rectangle of points to look for, such as a shape in a corner.
+///
+/// Patterns are written one row per line, top row first, with `X` for black, `O` for white,
+/// `.` for empty and `?` for points that may hold anything. Spaces are ignored:
+///
+/// ```text
+/// . . . .
+/// . . O .
+/// . X X .
+/// . . . ?
+/// ```
+#[derive(Clone, PartialEq, Eq, Debug, Hash)]
+pub struct Pattern {
+    pub height: usize,
+    pub width: usize,
+    /// Row by row from the bottom, like the board.
+    points: Vec<PatternPoint>,
+}
+
+impl Pattern {
+    pub fn parse(text: &str) -> Result<Self, PatternSearchError> {
+        let mut rows = Vec::new();
+        for (index, line) in text.lines().enumerate() {
+            let row = line
+                .chars()
+                .filter(|character| !character.is_whitespace())
+                .map(|character| match character {
+                    'X' | 'x' => Ok(PatternPoint::Black),
+                    'O' | 'o' => Ok(PatternPoint::White),
+                    '.' => Ok(PatternPoint::Empty),
+                    '?' => Ok(PatternPoint::Any),
+                    character => Err(PatternSearchError::InvalidCharacter {
+                        line: index + 1,
+                        character,
+                    }),
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            if !row.is_empty() {
+                rows.push(row);
+            }
+        }
+
+        let width = rows.first().map_or(0, Vec::len);
+        if rows.iter().any(|row| row.len() != width) {
+            return Err(PatternSearchError::RaggedRows);
+        }
+        let pattern = Pattern {
+            height: rows.len(),
+            width,
+            points: rows.into_iter().rev().flatten().collect(),
+        };
+        if !pattern
+            .points
+            .iter()
+            .any(|point| matches!(point, PatternPoint::Black | PatternPoint::White))
+        {
+            return Err(PatternSearchError::NoStones);
+        }
+        Ok(pattern)
+    }
+
+    pub fn get(&self, r: usize, c: usize) -> PatternPoint {
+        self.points[r * self.width + c]
+    }
+
+    /// The pattern rotated or mirrored.
+    pub fn transformed(&self, symmetry: Symmetry) -> Self {
+        let (height, width) = transformed_box(symmetry, (self.height, self.width));
+        let mut points = vec![PatternPoint::Any; height * width];
+        for r in 0..self.height {
+            for c in 0..self.width {
+                let (new_r, new_c) = transform_in_box(symmetry, (r, c), (self.height, self.width));
+                points[new_r * width + new_c] = self.get(r, c);
+            }
+        }
+        Pattern {
+            height,
+            width,
+            points,
+        }
+    }
+
+    /// The pattern with black and white swapped.
+    pub fn with_colours_swapped(&self) -> Self {
+        let points = self
+            .points
+            .iter()
+            .map(|point| match point {
+                PatternPoint::Black => PatternPoint::White,
+                PatternPoint::White => PatternPoint::Black,
+                other => *other,
+            })
+            .collect();
+        Pattern {
+            points,
+            ..self.clone()
+        }
+    }
+}
+
+// The height and width of a height × width box after the symmetry
+fn transformed_box(symmetry: Symmetry, (height, width): (usize, usize)) -> (usize, usize) {
+    match symmetry {
+        Symmetry::Rotate90
+        | Symmetry::Rotate270
+        | Symmetry::Transpose
+        | Symmetry::AntiTranspose => (width, height),
+        _ => (height, width),
+    }
+}
+
+// Where a point of a height × width box ends up when the box is rotated or mirrored and moved
+// back to the origin. Opposite corners of the box stay opposite, so they give the new origin
+fn transform_in_box(
+    symmetry: Symmetry,
+    point: (usize, usize),
+    (height, width): (usize, usize),
+) -> (usize, usize) {
+    let size = height.max(width);
+    let (r, c) = symmetry.apply(point, size);
+    let origin = symmetry.apply((0, 0), size);
+    let corner = symmetry.apply((height - 1, width - 1), size);
+    (r - origin.0.min(corner.0), c - origin.1.min(corner.1))
+}
+
+/// How a search is run.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct PatternQuery {
+    /// The board point of the pattern's bottom left corner, as it is written. The pattern is then
+    /// only looked for there and at the matching places of the other orientations, otherwise it is
+    /// looked for anywhere on the board.
+    pub anchor: Option<(usize, usize)>,
+    /// Also look for the pattern with black and white swapped.
+    pub colours_swapped: bool,
+}
+
+/// A place where the pattern formed in a game.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PatternMatch {
+    /// The index of the game in the database.
+    pub game: usize,
+    /// The number of moves played when the pattern formed.
+    pub move_number: usize,
+    /// The orientation the pattern was found in.
+    pub symmetry: Symmetry,
+    pub colours_swapped: bool,
+    /// The board point of the bottom left corner of the pattern as found.
+    pub offset: (usize, usize),
+    /// The next move of the game, `None` if it ended there.
+    pub next_move: Option<(Player, BadukMove)>,
+}
+
+/// A move played next where the pattern formed, with how often and how well it worked.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PatternContinuation {
+    /// The point inside the pattern as it is written, `None` for moves elsewhere and passes.
+    pub point: Option<(usize, usize)>,
+    /// The player in the pattern's colours.
+    pub player: Player,
+    pub count: u32,
+    /// The games the player of the move went on to win.
+    pub wins: u32,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct PatternResults {
+    pub matches: Vec<PatternMatch>,
+    /// The moves played next, most played first.
+    pub continuations: Vec<PatternContinuation>,
+}
+
+/// A game of the collection with every position of its main line.
+pub struct IndexedGame {
+    pub path: PathBuf,
+    pub info: GameInfo,
+    /// The main line, each move with the player who played it.
+    pub moves: Vec<(Player, BadukMove)>,
+    // The black and then the white stones of every position as bitboards, from the start
+    stones: Vec<u64>,
+    // Every point that ever held a black or a white stone, to skip games quickly
+    ever_black: Vec<u64>,
+    ever_white: Vec<u64>,
+}
+
+// One way the pattern can lie on the board
+struct Placement {
+    symmetry: Symmetry,
+    colours_swapped: bool,
+    offset: (usize, usize),
+    size: (usize, usize),
+    black: Vec<u64>,
+    white: Vec<u64>,
+    empty: Vec<u64>,
+}
+
+/// The main lines of a game collection indexed by position, to look for patterns in.
+///
+/// Every position is kept as a pair of bitboards, so checking a pattern placement is a few word
+/// comparisons, and games that never had stones on the points a placement needs are skipped
+/// without looking at their positions.
+pub struct PatternDatabase<const SIZE: usize> {
+    pub games: Vec<IndexedGame>,
+}
+
+impl<const SIZE: usize> Default for PatternDatabase<SIZE> {
+    fn default() -> Self {
+        PatternDatabase { games: Vec::new() }
+    }
+}
+
+impl<const SIZE: usize> PatternDatabase<SIZE> {
+    const WORDS: usize = (SIZE * SIZE).div_ceil(64);
+
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The number of indexed positions.
+    pub fn positions(&self) -> usize {
+        self.games
+            .iter()
+            .map(|game| game.stones.len() / (2 * Self::WORDS))
+            .sum()
+    }
+
+    /// Indexes the positions of the game's main line.
+    pub fn add_game(&mut self, path: &Path, tree: &GameTree<BadukClassical<SIZE>>) {
+        let mut indexed = IndexedGame {
+            path: path.to_path_buf(),
+            info: tree.info.clone(),
+            moves: Vec::new(),
+            stones: Vec::new(),
+            ever_black: vec![0; Self::WORDS],
+            ever_white: vec![0; Self::WORDS],
+        };
+        let mut game = &tree.root().game;
+        for (mv, node) in tree.main_line() {
+            Self::push_position(&mut indexed, game);
+            indexed.moves.push((game.turn, *mv));
+            game = &node.game;
+        }
+        Self::push_position(&mut indexed, game);
+        self.games.push(indexed);
+    }
+
+    fn push_position(indexed: &mut IndexedGame, game: &BadukClassical<SIZE>) {
+        let mut black = vec![0; Self::WORDS];
+        let mut white = vec![0; Self::WORDS];
+        for r in 0..SIZE {
+            for c in 0..SIZE {
+                match game.board.get_point(r, c) {
+                    Some(Point::Stone(Player::Black)) => set_bit(&mut black, r * SIZE + c),
+                    Some(Point::Stone(Player::White)) => set_bit(&mut white, r * SIZE + c),
+                    _ => {}
+                }
+            }
+        }
+        for (ever, words) in [
+            (&mut indexed.ever_black, &black),
+            (&mut indexed.ever_white, &white),
+        ] {
+            for (ever, word) in ever.iter_mut().zip(words) {
+                *ever |= word;
+            }
+        }
+        indexed.stones.extend(black);
+        indexed.stones.extend(white);
+    }
+
+    /// Indexes every game in the `.sgf` files of `directory`. Games on other board sizes are
+    /// skipped.
+    pub fn from_sgf_directory(directory: &Path) -> Result<Self, PatternSearchError> {
+        let io_error = |path: &Path| {
+            let path = path.to_path_buf();
+            move |source| PatternSearchError::Io { path, source }
+        };
+
+        let paths = sgf::sgf_files_in(directory).map_err(io_error(directory))?;
+
+        let mut database = Self::new();
+        for path in paths {
+            let contents = fs::read_to_string(&path).map_err(io_error(&path))?;
+            let collection = sgf::parse(&contents).map_err(|source| PatternSearchError::Sgf {
+                path: path.clone(),
+                source,
+            })?;
+            for sgf_root in &collection {
+                match sgf::to_game_tree::<SIZE>(sgf_root) {
+                    Ok(tree) => database.add_game(&path, &tree),
+                    Err(SgfError::BoardSize { .. }) => continue,
+                    Err(source) => {
+                        return Err(PatternSearchError::Sgf {
+                            path: path.clone(),
+                            source,
+                        })
+                    }
+                }
+            }
+        }
+        Ok(database)
+    }
+
+    /// Finds every place where the pattern formed, in any orientation. A pattern that stays on
+    /// the board counts once, when it forms, and again only if it is broken and forms again.
+    pub fn search(
+        &self,
+        pattern: &Pattern,
+        query: &PatternQuery,
+    ) -> Result<PatternResults, PatternSearchError> {
+        let placements = self.placements(pattern, query)?;
+        let mut results = PatternResults::default();
+        let mut continuations: HashMap<(Option<(usize, usize)>, Player), PatternContinuation> =
+            HashMap::new();
+
+        for (game_index, game) in self.games.iter().enumerate() {
+            let candidates = placements.iter().filter(|placement| {
+                contains(&game.ever_black, &placement.black)
+                    && contains(&game.ever_white, &placement.white)
+            });
+            for placement in candidates {
+                let mut matched_before = false;
+                for (move_number, position) in game.stones.chunks(2 * Self::WORDS).enumerate() {
+                    let (black, white) = position.split_at(Self::WORDS);
+                    let matched = contains(black, &placement.black)
+                        && contains(white, &placement.white)
+                        && !intersects(black, &placement.empty)
+                        && !intersects(white, &placement.empty);
+                    if matched && !matched_before {
+                        let next_move = game.moves.get(move_number).copied();
+                        if let Some((player, mv)) = next_move {
+                            let won =
+                                game.info.result.and_then(|result| result.winner()) == Some(player);
+                            let key = Self::continuation_key(placement, player, mv);
+                            let continuation =
+                                continuations
+                                    .entry(key)
+                                    .or_insert_with(|| PatternContinuation {
+                                        point: key.0,
+                                        player: key.1,
+                                        count: 0,
+                                        wins: 0,
+                                    });
+                            continuation.count += 1;
+                            continuation.wins += u32::from(won);
+                        }
+                        results.matches.push(PatternMatch {
+                            game: game_index,
+                            move_number,
+                            symmetry: placement.symmetry,
+                            colours_swapped: placement.colours_swapped,
+                            offset: placement.offset,
+                            next_move,
+                        });
+                    }
+                    matched_before = matched;
+                }
+            }
+        }
+
+        results
+            .matches
+            .sort_by_key(|found| (found.game, found.move_number));
+        results.continuations = continuations.into_values().collect();
+        results.continuations.sort_by(|a, b| {
+            b.count
+                .cmp(&a.count)
+                .then(a.point.cmp(&b.point))
+                .then((a.player == Player::White).cmp(&(b.player == Player::White)))
+        });
+        Ok(results)
+    }
+
+    // The move in the pattern's own frame and colours
+    fn continuation_key(
+        placement: &Placement,
+        player: Player,
+        mv: BadukMove,
+    ) -> (Option<(usize, usize)>, Player) {
+        let (height, width) = placement.size;
+        let (offset_r, offset_c) = placement.offset;
+        let point = match mv {
+            BadukMove::Play {
+                coordinates: (r, c),
+            } if (offset_r..offset_r + height).contains(&r)
+                && (offset_c..offset_c + width).contains(&c) =>
+            {
+                Some(transform_in_box(
+                    placement.symmetry.inverse(),
+                    (r - offset_r, c - offset_c),
+                    (height, width),
+                ))
+            }
+            _ => None,
+        };
+        let player = if placement.colours_swapped {
+            player.opponent()
+        } else {
+            player
+        };
+        (point, player)
+    }
+
+    // Every distinct way the pattern can lie on the board
+    fn placements(
+        &self,
+        pattern: &Pattern,
+        query: &PatternQuery,
+    ) -> Result<Vec<Placement>, PatternSearchError> {
+        if pattern.height > SIZE || pattern.width > SIZE {
+            return Err(PatternSearchError::TooLarge);
+        }
+        if let Some((r, c)) = query.anchor {
+            if r + pattern.height > SIZE || c + pattern.width > SIZE {
+                return Err(PatternSearchError::TooLarge);
+            }
+        }
+
+        let colourings: &[bool] = if query.colours_swapped {
+            &[false, true]
+        } else {
+            &[false]
+        };
+        let mut variants: Vec<(Pattern, Symmetry, bool)> = Vec::new();
+        for &colours_swapped in colourings {
+            for symmetry in Symmetry::ALL {
+                let mut variant = pattern.transformed(symmetry);
+                if colours_swapped {
+                    variant = variant.with_colours_swapped();
+                }
+                // A symmetric pattern would otherwise be found several times in the same place
+                if !variants.iter().any(|(known, _, _)| *known == variant) {
+                    variants.push((variant, symmetry, colours_swapped));
+                }
+            }
+        }
+
+        let mut placements = Vec::new();
+        for (variant, symmetry, colours_swapped) in variants {
+            let offsets: Vec<(usize, usize)> = match query.anchor {
+                Some((r, c)) => {
+                    let origin = symmetry.apply((r, c), SIZE);
+                    let corner =
+                        symmetry.apply((r + pattern.height - 1, c + pattern.width - 1), SIZE);
+                    vec![(origin.0.min(corner.0), origin.1.min(corner.1))]
+                }
+                None => (0..=SIZE - variant.height)
+                    .flat_map(|r| (0..=SIZE - variant.width).map(move |c| (r, c)))
+                    .collect(),
+            };
+            for offset in offsets {
+                let mut placement = Placement {
+                    symmetry,
+                    colours_swapped,
+                    offset,
+                    size: (variant.height, variant.width),
+                    black: vec![0; Self::WORDS],
+                    white: vec![0; Self::WORDS],
+                    empty: vec![0; Self::WORDS],
+                };
+                for r in 0..variant.height {
+                    for c in 0..variant.width {
+                        let index = (offset.0 + r) * SIZE + offset.1 + c;
+                        match variant.get(r, c) {
+                            PatternPoint::Black => set_bit(&mut placement.black, index),
+                            PatternPoint::White => set_bit(&mut placement.white, index),
+                            PatternPoint::Empty => set_bit(&mut placement.empty, index),
+                            PatternPoint::Any => {}
+                        }
+                    }
+                }
+                placements.push(placement);
+            }
+        }
+        Ok(placements)
+    }
+}
+
+fn set_bit(words: &mut [u64], index: usize) {
+    words[index / 64] |= 1 << (index % 64);
+}
+
+fn contains(words: &[u64], required: &[u64]) -> bool {
+    words
+        .iter()
+        .zip(required)
+        .all(|(word, required)| word & required == *required)
+}
+
+fn intersects(words: &[u64], other: &[u64]) -> bool {
+    words
+        .iter()
+        .zip(other)
+        .any(|(word, other)| word & other != 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    type Database = PatternDatabase<9>;
+
+    // Black's two stones under a white one, with the point above the left stone open
+    const SHAPE: &str = ". O\nX X";
+
+    // B 2-2, W 3-3, B 2-3 forms the shape at (2, 2) and W 3-2 then breaks it
+    const MOVES: [(usize, usize); 4] = [(2, 2), (3, 3), (2, 3), (3, 2)];
+
+    fn game(first: Player, moves: &[(usize, usize)]) -> GameTree<BadukClassical<9>> {
+        let mut record = String::from("(;SZ[9]");
+        let mut player = first;
+        for &(r, c) in moves {
+            let colour = if player == Player::Black { "B" } else { "W" };
+            record += &format!(";{colour}[{}]", sgf::format_point(r, c, 9));
+            player = player.opponent();
+        }
+        record += ")";
+        sgf::to_game_tree::<9>(&sgf::parse(&record).unwrap()[0]).unwrap()
+    }
+
+    fn database(games: &[GameTree<BadukClassical<9>>]) -> Database {
+        let mut database = Database::new();
+        for tree in games {
+            database.add_game(Path::new("games.sgf"), tree);
+        }
+        database
+    }
+
+    #[test]
+    fn points_transformed_in_a_box_stay_in_it_and_come_back() {
+        for size in [(2, 3), (3, 2), (1, 4), (3, 3)] {
+            for symmetry in Symmetry::ALL {
+                let transformed_size = transformed_box(symmetry, size);
+                let mut seen = Vec::new();
+                for r in 0..size.0 {
+                    for c in 0..size.1 {
+                        let point = transform_in_box(symmetry, (r, c), size);
+                        assert!(point.0 < transformed_size.0 && point.1 < transformed_size.1);
+                        assert!(!seen.contains(&point), "{symmetry:?} in {size:?}");
+                        seen.push(point);
+                        assert_eq!(
+                            transform_in_box(symmetry.inverse(), point, transformed_size),
+                            (r, c)
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn transformed_patterns_read_like_rotated_diagrams() {
+        let pattern = Pattern::parse(SHAPE).unwrap();
+        let rotated = (0..3).fold(pattern.clone(), |rotated, _| {
+            rotated.transformed(Symmetry::Rotate90)
+        });
+        assert_eq!(rotated, pattern.transformed(Symmetry::Rotate270));
+        assert_eq!(
+            rotated.transformed(Symmetry::Rotate90),
+            pattern,
+            "four quarter turns"
+        );
+        let tall = Pattern::parse("? X\n. O\nX X").unwrap();
+        assert_eq!(
+            (
+                tall.transformed(Symmetry::Transpose).height,
+                tall.transformed(Symmetry::Transpose).width
+            ),
+            (2, 3)
+        );
+    }
+
+    #[test]
+    fn patterns_are_found_in_every_orientation() {
+        let pattern = Pattern::parse(SHAPE).unwrap();
+        let mut games = vec![game(Player::Black, &MOVES)];
+        for symmetry in Symmetry::ALL {
+            let moves: Vec<(usize, usize)> = MOVES
+                .iter()
+                .map(|&point| symmetry.apply(point, 9))
+                .collect();
+            games.push(game(Player::Black, &moves));
+        }
+        let results = database(&games)
+            .search(&pattern, &PatternQuery::default())
+            .unwrap();
+
+        assert_eq!(results.matches.len(), games.len());
+        for (index, found) in results.matches.iter().enumerate() {
+            assert_eq!((found.game, found.move_number), (index, 3));
+            assert_eq!(
+                found.next_move.map(|(player, _)| player),
+                Some(Player::White)
+            );
+        }
+        // The breaking move lands on the same point of the pattern however the game was turned
+        assert_eq!(
+            results.continuations,
+            vec![PatternContinuation {
+                point: Some((1, 0)),
+                player: Player::White,
+                count: games.len() as u32,
+                wins: 0,
+            }]
+        );
+    }
+
+    #[test]
+    fn anchored_searches_only_look_in_one_place() {
+        let pattern = Pattern::parse(SHAPE).unwrap();
+        let games = [
+            game(Player::Black, &MOVES),
+            game(
+                Player::Black,
+                &MOVES.map(|point| Symmetry::Rotate180.apply(point, 9)),
+            ),
+        ];
+        let database = database(&games);
+        let search = |anchor| {
+            database
+                .search(
+                    &pattern,
+                    &PatternQuery {
+                        anchor: Some(anchor),
+                        colours_swapped: false,
+                    },
+                )
+                .unwrap()
+        };
+
+        assert_eq!(search((2, 2)).matches.len(), 2);
+        assert!(search((4, 4)).matches.is_empty());
+        assert!(matches!(
+            database.search(
+                &pattern,
+                &PatternQuery {
+                    anchor: Some((8, 8)),
+                    colours_swapped: false
+                }
+            ),
+            Err(PatternSearchError::TooLarge)
+        ));
+    }
+
+    #[test]
+    fn swapped_colours_are_only_found_when_asked_for() {
+        let pattern = Pattern::parse(SHAPE).unwrap();
+        let database = database(&[game(Player::White, &MOVES)]);
+
+        assert!(database
+            .search(&pattern, &PatternQuery::default())
+            .unwrap()
+            .matches
+            .is_empty());
+        let results = database
+            .search(
+                &pattern,
+                &PatternQuery {
+                    anchor: None,
+                    colours_swapped: true,
+                },
+            )
+            .unwrap();
+        assert_eq!(results.matches.len(), 1);
+        assert!(results.matches[0].colours_swapped);
+        // Black broke the shape, which is White's move in the pattern's colours
+        assert_eq!(results.continuations[0].player, Player::White);
+        assert_eq!(results.continuations[0].point, Some((1, 0)));
+    }
+}
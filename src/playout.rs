@@ -0,0 +1,96 @@
+use rand::seq::SliceRandom;
+use rand::Rng;
+use std::fmt;
+use std::str::FromStr;
+use thiserror::Error;
+
+use crate::game::{Player, StatelessGame};
+use crate::local_patterns::PatternWeights;
+
+/// How random play picks its moves, both for the random bot and for Monte Carlo playouts.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum PlayoutPolicy {
+    /// Any legal move, passing included, with the same chance.
+    Uniform,
+    /// Any move but obviously bad ones such as filling an own eye, passing only when no other
+    /// move is left.
+    #[default]
+    Reasonable,
+    /// Moves sampled by the weights of their local patterns and of captures and ataris, see
+    /// `local_patterns`.
+    Patterns,
+}
+
+/// Games that the `patterns` policy can play.
+pub trait PatternPlayout: StatelessGame {
+    /// A move sampled by the weights, `None` if there are no legal moves.
+    fn pattern_move<R: Rng>(&self, weights: &PatternWeights, rng: &mut R) -> Option<Self::Move>;
+}
+
+impl PlayoutPolicy {
+    /// A move picked by the policy, the weights are only used by `Patterns`.
+    pub fn choose_move<G: PatternPlayout, R: Rng>(
+        self,
+        game: &G,
+        weights: &PatternWeights,
+        rng: &mut R,
+    ) -> Option<G::Move> {
+        match self {
+            PlayoutPolicy::Uniform => game.list_all_legal_moves().choose(rng).copied(),
+            PlayoutPolicy::Reasonable => game.random_reasonable_move(rng),
+            PlayoutPolicy::Patterns => game.pattern_move(weights, rng),
+        }
+    }
+}
+
+/// Plays random moves chosen by the policy until the game ends or the move limit is reached, and
+/// returns who is ahead at that point.
+pub fn random_playout<G: PatternPlayout>(
+    mut game: G,
+    policy: PlayoutPolicy,
+    weights: &PatternWeights,
+    komi: f32,
+    max_moves: usize,
+    rng: &mut impl Rng,
+) -> Option<Player> {
+    for _ in 0..max_moves {
+        if game.is_game_over() {
+            break;
+        }
+        let Some(mv) = policy.choose_move(&game, weights, rng) else {
+            break;
+        };
+        match game.generate_next_board(&mv) {
+            Ok(next) => game = next,
+            Err(_) => break,
+        }
+    }
+    game.winner(komi)
+}
+
+impl fmt::Display for PlayoutPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PlayoutPolicy::Uniform => write!(f, "uniform"),
+            PlayoutPolicy::Reasonable => write!(f, "reasonable"),
+            PlayoutPolicy::Patterns => write!(f, "patterns"),
+        }
+    }
+}
+
+#[derive(Error, Debug)]
+#[error("Unknown Playout Policy \"{0}\"")]
+pub struct ParsePolicyError(String);
+
+impl FromStr for PlayoutPolicy {
+    type Err = ParsePolicyError;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        match text.trim() {
+            "uniform" => Ok(PlayoutPolicy::Uniform),
+            "reasonable" => Ok(PlayoutPolicy::Reasonable),
+            "patterns" => Ok(PlayoutPolicy::Patterns),
+            _ => Err(ParsePolicyError(text.to_string())),
+        }
+    }
+}
@@ -1,38 +1,68 @@
+use crate::bot::{
+    move_rng, parse_option, parse_patterns, parse_seed, BotConfig, BotError, GameBot,
+};
 use crate::game::{MoveError, StatelessGame};
-use rand::Rng;
+use crate::local_patterns::PlayoutPatterns;
+use crate::playout::{PatternPlayout, PlayoutPolicy};
+use crate::search::SearchContext;
 
-pub struct RandomBot<G: StatelessGame> {
-    _phantom: std::marker::PhantomData<G>,
+#[derive(Clone, Default, Debug)]
+pub struct RandomBotConfig {
+    /// Makes the moves reproducible, see `move_rng`.
+    pub seed: Option<u64>,
+    pub policy: PlayoutPolicy,
+    /// The weights the `patterns` policy samples by.
+    pub patterns: PlayoutPatterns,
 }
 
-pub trait GameBot {
-    type Game: StatelessGame;
-    fn new() -> Self;
-    fn select_move(
-        &self,
-        game: &Self::Game,
-    ) -> Result<<Self::Game as StatelessGame>::Move, MoveError>;
+impl BotConfig for RandomBotConfig {
+    fn options(&self) -> Vec<(&'static str, String)> {
+        vec![
+            (
+                "seed",
+                self.seed.map(|seed| seed.to_string()).unwrap_or_default(),
+            ),
+            ("policy", self.policy.to_string()),
+            ("patterns", self.patterns.to_string()),
+        ]
+    }
+
+    fn set_option(&mut self, name: &str, value: &str) -> Result<(), BotError> {
+        match name {
+            "seed" => self.seed = parse_seed(name, value)?,
+            "policy" => self.policy = parse_option(name, value)?,
+            "patterns" => self.patterns = parse_patterns(name, value)?,
+            _ => return Err(BotError::UnknownOption(name.to_string())),
+        }
+        Ok(())
+    }
 }
 
-impl<G: StatelessGame> GameBot for RandomBot<G> {
+#[derive(Clone)]
+pub struct RandomBot<G: StatelessGame> {
+    config: RandomBotConfig,
+    _phantom: std::marker::PhantomData<G>,
+}
+
+impl<G: PatternPlayout> GameBot for RandomBot<G> {
     type Game = G;
-    fn new() -> Self {
+    type Config = RandomBotConfig;
+    fn from_config(config: RandomBotConfig) -> Self {
         RandomBot {
+            config,
             _phantom: std::marker::PhantomData,
         }
     }
 
-    fn select_move(&self, game: &G) -> Result<G::Move, MoveError> {
-        let legal_moves = game.list_all_legal_moves();
-
-        if legal_moves.is_empty() {
-            return Err(MoveError::IllegalMove);
-        }
-
-        let mut rng = rand::thread_rng();
-        let random_index = rng.gen_range(0..legal_moves.len());
+    fn config(&self) -> &RandomBotConfig {
+        &self.config
+    }
 
-        Ok(legal_moves[random_index])
+    fn select_move(&self, game: &G, context: &SearchContext) -> Result<G::Move, MoveError> {
+        let mut rng = move_rng(self.config.seed, context.move_number);
+        self.config
+            .policy
+            .choose_move(game, &self.config.patterns.weights, &mut rng)
+            .ok_or(MoveError::NoLegalMoves)
     }
 }
-
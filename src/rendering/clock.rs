@@ -0,0 +1,81 @@
+use bevy::prelude::*;
+use std::time::Duration;
+
+use super::GRID_SIZE;
+use crate::clock::{PlayerClock, TimeControl};
+use crate::game::Player;
+
+const CLOCK_COLOR: Color = Color::srgb(0.75, 0.75, 0.75);
+const RUNNING_CLOCK_COLOR: Color = Color::WHITE;
+const FLAGGED_CLOCK_COLOR: Color = Color::srgb(1.0, 0.45, 0.4);
+
+/// The clock of one player, below the board on that player's side.
+#[derive(Component)]
+pub struct ClockText(pub Player);
+
+pub fn spawn_clocks(commands: &mut Commands) {
+    for (player, x) in [
+        (Player::Black, -GRID_SIZE / 4.0),
+        (Player::White, GRID_SIZE / 4.0),
+    ] {
+        commands.spawn((
+            Text2d::new(""),
+            TextFont {
+                font_size: 24.0,
+                ..default()
+            },
+            TextColor(CLOCK_COLOR),
+            Transform::from_xyz(x, -GRID_SIZE / 2.0 - 40.0, 3.0),
+            Visibility::Hidden,
+            ClockText(player),
+        ));
+    }
+}
+
+pub fn clock_color(clock: &PlayerClock, running: bool) -> Color {
+    if clock.flagged {
+        FLAGGED_CLOCK_COLOR
+    } else if running {
+        RUNNING_CLOCK_COLOR
+    } else {
+        CLOCK_COLOR
+    }
+}
+
+/// A time as shown on the clock, "1:05:00", "9:58" or "0:07".
+pub fn format_duration(duration: Duration) -> String {
+    // Round up so the clock only shows 0:00 once the time is really gone
+    let seconds = duration.as_secs() + u64::from(duration.subsec_nanos() > 0);
+    let (hours, minutes, seconds) = (seconds / 3600, seconds / 60 % 60, seconds % 60);
+    if hours > 0 {
+        format!("{hours}:{minutes:02}:{seconds:02}")
+    } else {
+        format!("{minutes}:{seconds:02}")
+    }
+}
+
+/// The clock face, e.g. "Black 9:58", "White 0:24 (3 periods)" or "Black 4:12 (8 stones)".
+pub fn clock_text(player: Player, control: &TimeControl, clock: &PlayerClock) -> String {
+    let colour = format!("{player:?}");
+    if clock.flagged {
+        return format!("{colour} out of time");
+    }
+    if !clock.in_overtime() {
+        return format!("{colour} {}", format_duration(clock.main_time));
+    }
+    match control {
+        TimeControl::ByoYomi { .. } => format!(
+            "{colour} {} ({} periods)",
+            format_duration(clock.period_time),
+            clock.periods
+        ),
+        TimeControl::Canadian { .. } => format!(
+            "{colour} {} ({} stones)",
+            format_duration(clock.period_time),
+            clock.stones
+        ),
+        TimeControl::Absolute { .. } | TimeControl::Fischer { .. } => {
+            format!("{colour} {}", format_duration(clock.main_time))
+        }
+    }
+}
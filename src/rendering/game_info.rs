@@ -0,0 +1,171 @@
+use bevy::prelude::*;
+
+use crate::game::GameInfo;
+
+#[derive(Component)]
+pub struct GameInfoPanel;
+
+/// The game information fields in the order the editor lists them.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum GameInfoField {
+    BlackPlayer,
+    BlackRank,
+    WhitePlayer,
+    WhiteRank,
+    Event,
+    Round,
+    Date,
+    Place,
+    Rules,
+    Komi,
+    Handicap,
+    MainTime,
+    Overtime,
+    Result,
+}
+
+impl GameInfoField {
+    pub const ALL: [GameInfoField; 14] = [
+        GameInfoField::BlackPlayer,
+        GameInfoField::BlackRank,
+        GameInfoField::WhitePlayer,
+        GameInfoField::WhiteRank,
+        GameInfoField::Event,
+        GameInfoField::Round,
+        GameInfoField::Date,
+        GameInfoField::Place,
+        GameInfoField::Rules,
+        GameInfoField::Komi,
+        GameInfoField::Handicap,
+        GameInfoField::MainTime,
+        GameInfoField::Overtime,
+        GameInfoField::Result,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            GameInfoField::BlackPlayer => "Black",
+            GameInfoField::BlackRank => "Black rank",
+            GameInfoField::WhitePlayer => "White",
+            GameInfoField::WhiteRank => "White rank",
+            GameInfoField::Event => "Event",
+            GameInfoField::Round => "Round",
+            GameInfoField::Date => "Date",
+            GameInfoField::Place => "Place",
+            GameInfoField::Rules => "Rules",
+            GameInfoField::Komi => "Komi",
+            GameInfoField::Handicap => "Handicap",
+            GameInfoField::MainTime => "Main time (s)",
+            GameInfoField::Overtime => "Overtime",
+            GameInfoField::Result => "Result",
+        }
+    }
+
+    pub fn get(self, info: &GameInfo) -> String {
+        let text = |field: &Option<String>| field.clone().unwrap_or_default();
+        match self {
+            GameInfoField::BlackPlayer => text(&info.black_player),
+            GameInfoField::BlackRank => text(&info.black_rank),
+            GameInfoField::WhitePlayer => text(&info.white_player),
+            GameInfoField::WhiteRank => text(&info.white_rank),
+            GameInfoField::Event => text(&info.event),
+            GameInfoField::Round => text(&info.round),
+            GameInfoField::Date => text(&info.date),
+            GameInfoField::Place => text(&info.place),
+            GameInfoField::Rules => text(&info.rules),
+            GameInfoField::Komi => info.komi.to_string(),
+            GameInfoField::Handicap => info.handicap.to_string(),
+            GameInfoField::MainTime => info.main_time.map(|t| t.to_string()).unwrap_or_default(),
+            GameInfoField::Overtime => text(&info.overtime),
+            GameInfoField::Result => info.result.map(|r| r.to_string()).unwrap_or_default(),
+        }
+    }
+
+    /// Stores the edited value, an empty text clears the field. Numbers that do not parse leave
+    /// the field unchanged.
+    pub fn set(self, info: &mut GameInfo, value: &str) {
+        let value = value.trim();
+        let text = || (!value.is_empty()).then(|| value.to_string());
+        match self {
+            GameInfoField::BlackPlayer => info.black_player = text(),
+            GameInfoField::BlackRank => info.black_rank = text(),
+            GameInfoField::WhitePlayer => info.white_player = text(),
+            GameInfoField::WhiteRank => info.white_rank = text(),
+            GameInfoField::Event => info.event = text(),
+            GameInfoField::Round => info.round = text(),
+            GameInfoField::Date => info.date = text(),
+            GameInfoField::Place => info.place = text(),
+            GameInfoField::Rules => info.rules = text(),
+            GameInfoField::Komi => {
+                if let Ok(komi) = value.parse() {
+                    info.komi = komi;
+                }
+            }
+            GameInfoField::Handicap => {
+                if let Ok(handicap) = value.parse() {
+                    info.handicap = handicap;
+                }
+            }
+            GameInfoField::MainTime => {
+                if value.is_empty() {
+                    info.main_time = None;
+                } else if let Ok(main_time) = value.parse() {
+                    info.main_time = Some(main_time);
+                }
+            }
+            GameInfoField::Overtime => info.overtime = text(),
+            GameInfoField::Result => {
+                if value.is_empty() {
+                    info.result = None;
+                } else if let Ok(result) = value.parse() {
+                    info.result = Some(result);
+                }
+            }
+        }
+    }
+}
+
+pub fn spawn_game_info_panel(commands: &mut Commands) {
+    commands.spawn((
+        Text::new(""),
+        TextFont {
+            font_size: 18.0,
+            ..default()
+        },
+        Node {
+            position_type: PositionType::Absolute,
+            top: Val::Px(90.0),
+            left: Val::Px(120.0),
+            padding: UiRect::all(Val::Px(16.0)),
+            ..default()
+        },
+        BackgroundColor(Color::srgba(0.0, 0.0, 0.0, 0.85)),
+        Visibility::Hidden,
+        GameInfoPanel,
+    ));
+}
+
+/// The editor contents, one "label: value" line per field with the selected field marked.
+pub fn game_info_text(values: &[String], selected: usize) -> String {
+    let mut lines = vec!["Game information".to_string(), String::new()];
+    for (index, (field, value)) in GameInfoField::ALL.iter().zip(values).enumerate() {
+        let (marker, cursor) = if index == selected {
+            ("> ", "_")
+        } else {
+            ("  ", "")
+        };
+        lines.push(format!("{marker}{}: {value}{cursor}", field.label()));
+    }
+    lines.push(String::new());
+    lines.push("Up/Down: select, Enter: apply, Escape: cancel".to_string());
+    lines.join("\n")
+}
+
+/// A player as shown in the status line, e.g. "Lee Sedol 9p", falling back to the colour.
+pub fn player_name(name: &Option<String>, rank: &Option<String>, colour: &str) -> String {
+    match (name, rank) {
+        (Some(name), Some(rank)) => format!("{name} {rank}"),
+        (Some(name), None) => name.clone(),
+        (None, _) => colour.to_string(),
+    }
+}
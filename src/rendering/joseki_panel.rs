@@ -0,0 +1,71 @@
+use bevy::prelude::*;
+
+use super::markup::evaluation_symbol;
+use crate::game::{move_label, BadukMove, Player};
+use crate::joseki::{Corner, JosekiContinuation};
+
+const MAX_LISTED_CONTINUATIONS: usize = 12;
+
+#[derive(Component)]
+pub struct JosekiPanel;
+
+pub fn spawn_joseki_panel(commands: &mut Commands) {
+    commands.spawn((
+        Text::new(""),
+        TextFont {
+            font_size: 16.0,
+            ..default()
+        },
+        Node {
+            position_type: PositionType::Absolute,
+            top: Val::Px(90.0),
+            left: Val::Px(10.0),
+            padding: UiRect::all(Val::Px(10.0)),
+            ..default()
+        },
+        BackgroundColor(Color::srgba(0.0, 0.0, 0.0, 0.75)),
+        Visibility::Hidden,
+        JosekiPanel,
+    ));
+}
+
+/// The panel contents, one line per known continuation in the corner, e.g.
+/// "R14 (W) !  Approach, 12 lines".
+pub fn joseki_text(corner: Option<Corner>, continuations: &[JosekiContinuation]) -> String {
+    let Some(corner) = corner else {
+        return "Joseki\n\nPlay in a corner to see known continuations".to_string();
+    };
+    let mut lines = vec![format!("Joseki: {}", corner.name()), String::new()];
+    if continuations.is_empty() {
+        lines.push("Not in the dictionary".to_string());
+    }
+    for continuation in continuations.iter().take(MAX_LISTED_CONTINUATIONS) {
+        let mv = match continuation.mv {
+            BadukMove::Pass => "Tenuki".to_string(),
+            mv => move_label(&mv),
+        };
+        let colour = match continuation.player {
+            Player::Black => "B",
+            Player::White => "W",
+        };
+        let evaluation = continuation.evaluation.map_or("", evaluation_symbol);
+        let lines_below = match continuation.variations {
+            1 => "1 line".to_string(),
+            count => format!("{count} lines"),
+        };
+        let description = match &continuation.label {
+            Some(label) => format!("{label}, {lines_below}"),
+            None => lines_below,
+        };
+        lines.push(format!("{mv} ({colour}) {evaluation}  {description}"));
+    }
+    if continuations.len() > MAX_LISTED_CONTINUATIONS {
+        lines.push(format!(
+            "... and {} more",
+            continuations.len() - MAX_LISTED_CONTINUATIONS
+        ));
+    }
+    lines.push(String::new());
+    lines.push("J: hide".to_string());
+    lines.join("\n")
+}
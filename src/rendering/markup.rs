@@ -0,0 +1,159 @@
+use bevy::prelude::*;
+
+use super::{board_to_world, BOARD_COLOR, CELL_SIZE};
+use crate::game::{
+    Board, Markup, MoveEvaluation, NodeAnnotations, Player, Point, PositionJudgement,
+};
+
+/// Labels and dimmed points are entities, respawned with the stones whenever the position changes.
+#[derive(Component)]
+pub struct MarkupEntity;
+
+/// Draws outlines in white on black stones and in black everywhere else.
+fn markup_color<const SIZE: usize>(board: &Board<SIZE>, (r, c): (usize, usize)) -> Color {
+    match board.get_point(r, c) {
+        Some(Point::Stone(Player::Black)) => Color::WHITE,
+        _ => Color::BLACK,
+    }
+}
+
+/// Draws the shapes, arrows and lines of the current node. Gizmos are immediate mode, so this runs
+/// every frame.
+pub fn draw_markup<const SIZE: usize>(gizmos: &mut Gizmos, board: &Board<SIZE>, markup: &[Markup]) {
+    let size = CELL_SIZE * 0.3;
+
+    for item in markup {
+        match item {
+            Markup::Triangle(point) => {
+                let center = board_to_world(*point);
+                let corners = [
+                    center + Vec2::new(0.0, size),
+                    center + Vec2::new(-size * 0.87, -size * 0.5),
+                    center + Vec2::new(size * 0.87, -size * 0.5),
+                    center + Vec2::new(0.0, size),
+                ];
+                gizmos.linestrip_2d(corners, markup_color(board, *point));
+            }
+            Markup::Square(point) => {
+                gizmos.rect_2d(
+                    board_to_world(*point),
+                    Vec2::splat(size * 1.4),
+                    markup_color(board, *point),
+                );
+            }
+            Markup::Circle(point) => {
+                gizmos.circle_2d(board_to_world(*point), size, markup_color(board, *point));
+            }
+            Markup::Cross(point) => {
+                let center = board_to_world(*point);
+                let color = markup_color(board, *point);
+                gizmos.line_2d(
+                    center - Vec2::splat(size),
+                    center + Vec2::splat(size),
+                    color,
+                );
+                gizmos.line_2d(
+                    center + Vec2::new(-size, size),
+                    center + Vec2::new(size, -size),
+                    color,
+                );
+            }
+            Markup::Arrow(from, to) => {
+                gizmos.arrow_2d(board_to_world(*from), board_to_world(*to), Color::BLACK);
+            }
+            Markup::Line(from, to) => {
+                gizmos.line_2d(board_to_world(*from), board_to_world(*to), Color::BLACK);
+            }
+            Markup::Label(..) | Markup::Dimmed(_) => {}
+        }
+    }
+}
+
+pub fn spawn_markup_entities<const SIZE: usize>(
+    commands: &mut Commands,
+    board: &Board<SIZE>,
+    markup: &[Markup],
+) {
+    for item in markup {
+        match item {
+            Markup::Label(point, text) => {
+                let position = board_to_world(*point);
+                // Cover the grid lines so the label stays readable on an empty point
+                if board.get_point(point.0, point.1) == Some(Point::Empty) {
+                    commands.spawn((
+                        Sprite {
+                            color: BOARD_COLOR,
+                            custom_size: Some(Vec2::splat(CELL_SIZE * 0.7)),
+                            ..default()
+                        },
+                        Transform::from_xyz(position.x, position.y, 2.5),
+                        MarkupEntity,
+                    ));
+                }
+                commands.spawn((
+                    Text2d::new(text.clone()),
+                    TextFont {
+                        font_size: CELL_SIZE * 0.5,
+                        ..default()
+                    },
+                    TextColor(markup_color(board, *point)),
+                    Transform::from_xyz(position.x, position.y, 3.0),
+                    MarkupEntity,
+                ));
+            }
+            Markup::Dimmed(point) => {
+                let position = board_to_world(*point);
+                commands.spawn((
+                    Sprite {
+                        color: BOARD_COLOR.with_alpha(0.6),
+                        custom_size: Some(Vec2::splat(CELL_SIZE)),
+                        ..default()
+                    },
+                    Transform::from_xyz(position.x, position.y, 2.5),
+                    MarkupEntity,
+                ));
+            }
+            _ => {}
+        }
+    }
+}
+
+pub fn evaluation_symbol(evaluation: MoveEvaluation) -> &'static str {
+    match evaluation {
+        MoveEvaluation::Good => "!",
+        MoveEvaluation::Bad => "?",
+        MoveEvaluation::Doubtful => "?!",
+        MoveEvaluation::Interesting => "!?",
+    }
+}
+
+/// The text shown below the board: the judgements followed by the comment.
+pub fn annotation_text(annotations: &NodeAnnotations) -> String {
+    let mut lines = Vec::new();
+
+    let evaluation = annotations
+        .move_evaluation
+        .map(|evaluation| match evaluation {
+            MoveEvaluation::Good => "Good move",
+            MoveEvaluation::Bad => "Bad move",
+            MoveEvaluation::Doubtful => "Doubtful move",
+            MoveEvaluation::Interesting => "Interesting move",
+        });
+    let judgement = annotations
+        .position_judgement
+        .map(|judgement| match judgement {
+            PositionJudgement::GoodForBlack => "Good for Black",
+            PositionJudgement::GoodForWhite => "Good for White",
+            PositionJudgement::Even => "Even position",
+            PositionJudgement::Unclear => "Unclear position",
+        });
+    let summary: Vec<&str> = evaluation.into_iter().chain(judgement).collect();
+    if !summary.is_empty() {
+        lines.push(summary.join(", "));
+    }
+    if let Some(comment) = &annotations.comment {
+        lines.push(comment.clone());
+    }
+
+    lines.join("\n")
+}
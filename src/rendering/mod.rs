@@ -1,8 +1,20 @@
 use bevy::prelude::*;
 
+pub mod clock;
+pub mod game_info;
+pub mod joseki_panel;
+pub mod markup;
+pub mod tree_panel;
+
+use clock::spawn_clocks;
+use game_info::spawn_game_info_panel;
+use joseki_panel::spawn_joseki_panel;
+use tree_panel::{spawn_tree_panel, PANEL_WIDTH};
+
 pub const BOARD_SIZE: usize = 19;
 pub const GRID_SIZE: f32 = 600.0;
 pub const CELL_SIZE: f32 = GRID_SIZE / (BOARD_SIZE - 1) as f32;
+pub const BOARD_COLOR: Color = Color::srgb(0.9, 0.7, 0.4); // Wood-like color
 
 #[derive(Component)]
 pub struct Board;
@@ -10,17 +22,95 @@ pub struct Board;
 #[derive(Component)]
 pub struct GridLine;
 
+#[derive(Component)]
+pub struct StatusText;
+
+#[derive(Component)]
+pub struct CommentText;
+
+/// Explains why the last move was refused, just above the board.
+#[derive(Component)]
+pub struct MoveErrorText;
+
+pub fn board_to_world((row, col): (usize, usize)) -> Vec2 {
+    Vec2::new(
+        (col as f32 - (BOARD_SIZE - 1) as f32 / 2.0) * CELL_SIZE,
+        (row as f32 - (BOARD_SIZE - 1) as f32 / 2.0) * CELL_SIZE,
+    )
+}
+
+/// Converts a world position to the nearest board intersection, if it is on the board.
+pub fn world_to_board(world_position: Vec2) -> Option<(usize, usize)> {
+    let board_x = world_position.x + (BOARD_SIZE - 1) as f32 * CELL_SIZE / 2.0;
+    let board_y = world_position.y + (BOARD_SIZE - 1) as f32 * CELL_SIZE / 2.0;
+
+    let col = (board_x / CELL_SIZE).round();
+    let row = (board_y / CELL_SIZE).round();
+
+    if row < 0.0 || col < 0.0 || row as usize >= BOARD_SIZE || col as usize >= BOARD_SIZE {
+        return None;
+    }
+    Some((row as usize, col as usize))
+}
+
 pub fn setup(
     mut commands: Commands,
     mut meshes: ResMut<Assets<Mesh>>,
     mut materials: ResMut<Assets<ColorMaterial>>,
 ) {
-    commands.spawn(Camera2d);
+    // Shift the camera so the board sits left of the tree panel
+    commands.spawn((Camera2d, Transform::from_xyz(PANEL_WIDTH / 2.0, 0.0, 0.0)));
+    spawn_tree_panel(&mut commands);
+    spawn_game_info_panel(&mut commands);
+    spawn_joseki_panel(&mut commands);
+    spawn_clocks(&mut commands);
+
+    commands.spawn((
+        Text::new(""),
+        TextFont {
+            font_size: 20.0,
+            ..default()
+        },
+        Node {
+            position_type: PositionType::Absolute,
+            top: Val::Px(10.0),
+            left: Val::Px(10.0),
+            ..default()
+        },
+        StatusText,
+    ));
+
+    commands.spawn((
+        Text::new(""),
+        TextFont {
+            font_size: 16.0,
+            ..default()
+        },
+        Node {
+            position_type: PositionType::Absolute,
+            bottom: Val::Px(10.0),
+            left: Val::Px(10.0),
+            max_width: Val::Px(GRID_SIZE + 140.0),
+            ..default()
+        },
+        CommentText,
+    ));
+
+    commands.spawn((
+        Text2d::new(""),
+        TextFont {
+            font_size: 18.0,
+            ..default()
+        },
+        TextColor(Color::srgb(1.0, 0.45, 0.4)),
+        Transform::from_xyz(0.0, GRID_SIZE / 2.0 + 38.0, 3.0),
+        MoveErrorText,
+    ));
 
     // Create board background
     commands.spawn((
         Sprite {
-            color: Color::srgb(0.9, 0.7, 0.4), // Wood-like color
+            color: BOARD_COLOR,
             custom_size: Some(Vec2::new(GRID_SIZE + 40.0, GRID_SIZE + 40.0)),
             ..default()
         },
@@ -55,9 +145,15 @@ pub fn setup(
 
     // Add star points (handicap points)
     let star_points = [
-        (3, 3), (3, 9), (3, 15),
-        (9, 3), (9, 9), (9, 15),
-        (15, 3), (15, 9), (15, 15),
+        (3, 3),
+        (3, 9),
+        (3, 15),
+        (9, 3),
+        (9, 9),
+        (9, 15),
+        (15, 3),
+        (15, 9),
+        (15, 15),
     ];
 
     let star_material = materials.add(Color::BLACK);
@@ -72,4 +168,4 @@ pub fn setup(
             Transform::from_xyz(x, y, 1.0),
         ));
     }
-}
\ No newline at end of file
+}
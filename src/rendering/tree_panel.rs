@@ -0,0 +1,154 @@
+use bevy::prelude::*;
+
+use super::markup::evaluation_symbol;
+use crate::game::{move_label, BadukClassical, BadukMove, GameNode, GamePointer, GameTree, Player};
+
+pub const PANEL_WIDTH: f32 = 280.0;
+const MAX_VISIBLE_ROWS: usize = 36;
+const ROW_INDENT: f32 = 14.0;
+
+const ROW_COLOR: Color = Color::NONE;
+const CURRENT_ROW_COLOR: Color = Color::srgb(0.35, 0.35, 0.6);
+
+#[derive(Component)]
+pub struct TreePanel;
+
+/// A clickable move in the tree panel, holding the moves from the root to reach it.
+#[derive(Component)]
+pub struct TreeNodeButton(pub Vec<BadukMove>);
+
+pub struct TreeRow {
+    pub moves: Vec<BadukMove>,
+    pub indent: usize,
+    pub label: String,
+}
+
+pub fn spawn_tree_panel(commands: &mut Commands) {
+    commands.spawn((
+        Node {
+            position_type: PositionType::Absolute,
+            top: Val::Px(10.0),
+            right: Val::Px(10.0),
+            bottom: Val::Px(10.0),
+            width: Val::Px(PANEL_WIDTH),
+            flex_direction: FlexDirection::Column,
+            overflow: Overflow::clip(),
+            ..default()
+        },
+        BackgroundColor(Color::srgba(0.0, 0.0, 0.0, 0.6)),
+        TreePanel,
+    ));
+}
+
+/// Lists the tree top to bottom. The main line stays at the same indentation and each variation
+/// is indented below the move it branches from.
+pub fn collect_tree_rows<const SIZE: usize>(tree: &GameTree<BadukClassical<SIZE>>) -> Vec<TreeRow> {
+    let mut rows = Vec::new();
+    collect_line(tree.root(), &mut Vec::new(), 0, &mut rows);
+    rows
+}
+
+fn collect_line<const SIZE: usize>(
+    mut node: &GameNode<BadukClassical<SIZE>>,
+    moves: &mut Vec<BadukMove>,
+    indent: usize,
+    rows: &mut Vec<TreeRow>,
+) {
+    let depth = moves.len();
+
+    while let Some((main_move, main_child)) = node.main_child() {
+        moves.push(*main_move);
+        rows.push(tree_row(moves, indent, main_child));
+
+        for (variation_move, variation) in node.children.iter().skip(1) {
+            let mut variation_moves = moves[..moves.len() - 1].to_vec();
+            variation_moves.push(*variation_move);
+            rows.push(tree_row(&variation_moves, indent + 1, variation));
+            collect_line(variation, &mut variation_moves, indent + 1, rows);
+        }
+
+        node = main_child;
+    }
+
+    moves.truncate(depth);
+}
+
+fn tree_row<const SIZE: usize>(
+    moves: &[BadukMove],
+    indent: usize,
+    node: &GameNode<BadukClassical<SIZE>>,
+) -> TreeRow {
+    let mover = match node.game.turn.opponent() {
+        Player::Black => "B",
+        Player::White => "W",
+    };
+    let mut label = format!(
+        "{}. {mover} {}",
+        moves.len(),
+        move_label(moves.last().unwrap())
+    );
+    if let Some(evaluation) = node.annotations.move_evaluation {
+        label.push_str(evaluation_symbol(evaluation));
+    }
+    if node.annotations.comment.is_some() {
+        label.push_str(" *");
+    }
+
+    TreeRow {
+        moves: moves.to_vec(),
+        indent,
+        label,
+    }
+}
+
+/// Replaces the panel contents with the rows around the current node.
+pub fn rebuild_tree_panel<const SIZE: usize>(
+    commands: &mut Commands,
+    panel: Entity,
+    tree: &GameTree<BadukClassical<SIZE>>,
+    pointer: &GamePointer<BadukClassical<SIZE>>,
+) {
+    let rows = collect_tree_rows(tree);
+    let current = rows
+        .iter()
+        .position(|row| row.moves == pointer.moves())
+        .unwrap_or(0);
+    let first = current
+        .saturating_sub(MAX_VISIBLE_ROWS / 2)
+        .min(rows.len().saturating_sub(MAX_VISIBLE_ROWS));
+
+    commands.entity(panel).despawn_descendants();
+    commands.entity(panel).with_children(|parent| {
+        for row in rows.into_iter().skip(first).take(MAX_VISIBLE_ROWS) {
+            let color = if row.moves == pointer.moves() {
+                CURRENT_ROW_COLOR
+            } else {
+                ROW_COLOR
+            };
+            parent
+                .spawn((
+                    Button,
+                    Node {
+                        padding: UiRect {
+                            left: Val::Px(8.0 + row.indent as f32 * ROW_INDENT),
+                            top: Val::Px(1.0),
+                            bottom: Val::Px(1.0),
+                            ..default()
+                        },
+                        ..default()
+                    },
+                    BackgroundColor(color),
+                    TreeNodeButton(row.moves),
+                ))
+                .with_children(|button| {
+                    button.spawn((
+                        Text::new(row.label),
+                        TextFont {
+                            font_size: 14.0,
+                            ..default()
+                        },
+                    ));
+                });
+        }
+    });
+}
@@ -0,0 +1,110 @@
+use std::fmt;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::clock::{PlayerClock, TimeControl};
+use crate::game::DEFAULT_KOMI;
+
+// Time kept back on every move for the move to reach the clock
+const SAFETY_MARGIN: Duration = Duration::from_millis(300);
+// Share of an overtime period a bot may use, the rest absorbs slow playouts near the end
+const PERIOD_SHARE: f32 = 0.8;
+// Share of the Fischer increment a bot may spend on top of its main time allocation
+const INCREMENT_SHARE: f32 = 0.8;
+
+/// What a bot knows about the search it is asked to run besides the position.
+#[derive(Clone, Debug)]
+pub struct SearchContext {
+    /// The clock of the player to move, in timed games.
+    pub time_left: Option<PlayerClock>,
+    pub time_control: Option<TimeControl>,
+    /// The number of moves played so far.
+    pub move_number: usize,
+    pub board_size: usize,
+    pub komi: f32,
+    /// Set from outside to make the bot return its best move so far.
+    pub stop: Arc<AtomicBool>,
+}
+
+impl SearchContext {
+    /// An untimed search at the start of a game.
+    pub fn new(board_size: usize) -> Self {
+        SearchContext {
+            time_left: None,
+            time_control: None,
+            move_number: 0,
+            board_size,
+            komi: DEFAULT_KOMI,
+            stop: Arc::default(),
+        }
+    }
+
+    pub fn stop_requested(&self) -> bool {
+        self.stop.load(Ordering::Relaxed)
+    }
+
+    /// The number of moves the player to move is still expected to play. The estimate never drops
+    /// below one move per line so that time is not all spent once a game runs long.
+    pub fn expected_moves_left(&self) -> u32 {
+        let game_length = self.board_size * self.board_size * 2 / 3;
+        let moves_left = game_length.saturating_sub(self.move_number) / 2;
+        moves_left.max(self.board_size).max(1) as u32
+    }
+
+    /// How long to think about this move, `None` in untimed games. Main time is spread over the
+    /// expected remaining moves while overtime periods, which start over every move, are mostly
+    /// used up.
+    pub fn time_budget(&self) -> Option<Duration> {
+        let (clock, control) = (self.time_left?, self.time_control?);
+        if clock.flagged {
+            return Some(Duration::ZERO);
+        }
+
+        let from_main_time = clock.main_time / self.expected_moves_left();
+        let budget = match control {
+            TimeControl::Absolute { .. } => from_main_time,
+            TimeControl::ByoYomi { .. } => {
+                // A period is available again on every move, so it adds to the main time share
+                from_main_time + clock.period_time.mul_f32(PERIOD_SHARE)
+            }
+            TimeControl::Canadian { .. } => {
+                if clock.in_overtime() {
+                    (clock.period_time / clock.stones.max(1)).mul_f32(PERIOD_SHARE)
+                } else {
+                    from_main_time
+                }
+            }
+            TimeControl::Fischer { increment, .. } => {
+                (from_main_time + increment.mul_f32(INCREMENT_SHARE)).min(clock.main_time / 2)
+            }
+        };
+        Some(
+            budget
+                .min(clock.time_for_move())
+                .saturating_sub(SAFETY_MARGIN),
+        )
+    }
+}
+
+/// What a bot's last search did.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct SearchReport {
+    /// Playouts run for this move.
+    pub playouts: u32,
+    /// Playouts carried over from the previous search and from pondering.
+    pub reused_visits: u32,
+    pub elapsed: Duration,
+}
+
+impl fmt::Display for SearchReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} playouts in {:.1}s, {} reused",
+            self.playouts,
+            self.elapsed.as_secs_f32(),
+            self.reused_visits
+        )
+    }
+}
@@ -0,0 +1,731 @@
+use crate::game::{
+    BadukClassical, BadukMove, Board, GameInfo, GameNode, GameTree, Markup, MoveError,
+    MoveEvaluation, NodeAnnotations, Player, Point, PositionJudgement,
+};
+use indexmap::IndexMap;
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+
+// Smart Game Format, see https://www.red-bean.com/sgf/sgf4.html
+//
+// Collection = GameTree { GameTree }
+// GameTree   = "(" Sequence { GameTree } ")"
+// Sequence   = Node { Node }
+// Node       = ";" { Property }
+// Property   = PropIdent PropValue { PropValue }
+//
+// A sequence is flattened into a chain of nodes, so every SgfNode has the nodes that follow it
+// (either the next node in its sequence or the roots of the sub-trees) as children.
+
+type PointPair = ((usize, usize), (usize, usize));
+
+#[derive(Error, Debug)]
+pub enum SgfError {
+    #[error("Unexpected End of SGF Input")]
+    UnexpectedEnd,
+    #[error("Unexpected Character '{found}' at Byte {position}")]
+    UnexpectedChar { found: char, position: usize },
+    #[error("Invalid SGF Point \"{0}\"")]
+    InvalidPoint(String),
+    #[error("Board Size {found} Does Not Match {expected}")]
+    BoardSize { found: usize, expected: usize },
+    #[error("Setup Stones Are Only Supported in the Root Node")]
+    SetupOutsideRoot,
+    #[error("Variations Play the Same Move With Both Colours")]
+    ConflictingVariations,
+    #[error("Invalid Move in Game Record: {0}")]
+    InvalidMove(#[from] MoveError),
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SgfNode {
+    pub properties: IndexMap<String, Vec<String>>,
+    pub children: Vec<SgfNode>,
+}
+
+impl SgfNode {
+    pub fn get(&self, id: &str) -> Option<&str> {
+        self.properties
+            .get(id)
+            .and_then(|values| values.first())
+            .map(String::as_str)
+    }
+
+    pub fn get_all(&self, id: &str) -> &[String] {
+        self.properties.get(id).map(Vec::as_slice).unwrap_or(&[])
+    }
+
+    pub fn has(&self, id: &str) -> bool {
+        self.properties.contains_key(id)
+    }
+
+    pub fn push(&mut self, id: &str, value: impl Into<String>) {
+        self.properties
+            .entry(id.to_string())
+            .or_default()
+            .push(value.into());
+    }
+}
+
+pub fn parse(input: &str) -> Result<Vec<SgfNode>, SgfError> {
+    let mut parser = Parser { input, position: 0 };
+    let mut collection = Vec::new();
+
+    parser.skip_whitespace();
+    while parser.peek().is_some() {
+        collection.push(parser.parse_game_tree()?);
+        parser.skip_whitespace();
+    }
+
+    Ok(collection)
+}
+
+/// The `.sgf` files in the directory, sorted by path. Subdirectories are not searched.
+pub fn sgf_files_in(directory: &Path) -> std::io::Result<Vec<PathBuf>> {
+    let mut paths: Vec<PathBuf> = std::fs::read_dir(directory)?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| {
+            path.extension()
+                .is_some_and(|ext| ext.eq_ignore_ascii_case("sgf"))
+        })
+        .collect();
+    paths.sort();
+    Ok(paths)
+}
+
+pub fn write(collection: &[SgfNode]) -> String {
+    let mut output = String::new();
+    for root in collection {
+        write_game_tree(root, &mut output);
+        output.push('\n');
+    }
+    output
+}
+
+fn write_game_tree(mut node: &SgfNode, output: &mut String) {
+    output.push('(');
+    loop {
+        write_node(node, output);
+        match node.children.as_slice() {
+            [next] => node = next,
+            variations => {
+                for variation in variations {
+                    write_game_tree(variation, output);
+                }
+                break;
+            }
+        }
+    }
+    output.push(')');
+}
+
+fn write_node(node: &SgfNode, output: &mut String) {
+    output.push(';');
+    for (id, values) in &node.properties {
+        output.push_str(id);
+        for value in values {
+            output.push('[');
+            for ch in value.chars() {
+                if ch == ']' || ch == '\\' {
+                    output.push('\\');
+                }
+                output.push(ch);
+            }
+            output.push(']');
+        }
+    }
+}
+
+struct Parser<'a> {
+    input: &'a str,
+    position: usize,
+}
+
+impl Parser<'_> {
+    fn peek(&self) -> Option<char> {
+        self.input[self.position..].chars().next()
+    }
+
+    fn next(&mut self) -> Option<char> {
+        let ch = self.peek()?;
+        self.position += ch.len_utf8();
+        Some(ch)
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.peek().is_some_and(char::is_whitespace) {
+            self.next();
+        }
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), SgfError> {
+        self.skip_whitespace();
+        let position = self.position;
+        match self.next() {
+            Some(ch) if ch == expected => Ok(()),
+            Some(found) => Err(SgfError::UnexpectedChar { found, position }),
+            None => Err(SgfError::UnexpectedEnd),
+        }
+    }
+
+    fn parse_game_tree(&mut self) -> Result<SgfNode, SgfError> {
+        self.expect('(')?;
+
+        // Parse the sequence, then the variations hanging off its last node
+        let mut sequence = Vec::new();
+        self.skip_whitespace();
+        while self.peek() == Some(';') {
+            sequence.push(self.parse_node()?);
+            self.skip_whitespace();
+        }
+        if sequence.is_empty() {
+            return match self.peek() {
+                Some(found) => Err(SgfError::UnexpectedChar {
+                    found,
+                    position: self.position,
+                }),
+                None => Err(SgfError::UnexpectedEnd),
+            };
+        }
+
+        let mut variations = Vec::new();
+        while self.peek() == Some('(') {
+            variations.push(self.parse_game_tree()?);
+            self.skip_whitespace();
+        }
+        self.expect(')')?;
+
+        // Fold the sequence into a chain, innermost node first
+        let mut node = sequence.pop().unwrap();
+        node.children = variations;
+        while let Some(mut parent) = sequence.pop() {
+            parent.children.push(node);
+            node = parent;
+        }
+
+        Ok(node)
+    }
+
+    fn parse_node(&mut self) -> Result<SgfNode, SgfError> {
+        self.expect(';')?;
+        let mut node = SgfNode::default();
+
+        loop {
+            self.skip_whitespace();
+            let start = self.position;
+            while self.peek().is_some_and(|ch| ch.is_ascii_alphabetic()) {
+                self.next();
+            }
+            if start == self.position {
+                break;
+            }
+            // FF[1-3] allowed lowercase letters in identifiers, only the uppercase ones are significant
+            let id: String = self.input[start..self.position]
+                .chars()
+                .filter(char::is_ascii_uppercase)
+                .collect();
+
+            let mut values = Vec::new();
+            self.skip_whitespace();
+            while self.peek() == Some('[') {
+                values.push(self.parse_value()?);
+                self.skip_whitespace();
+            }
+            if values.is_empty() {
+                return match self.peek() {
+                    Some(found) => Err(SgfError::UnexpectedChar {
+                        found,
+                        position: self.position,
+                    }),
+                    None => Err(SgfError::UnexpectedEnd),
+                };
+            }
+
+            node.properties.entry(id).or_default().extend(values);
+        }
+
+        Ok(node)
+    }
+
+    fn parse_value(&mut self) -> Result<String, SgfError> {
+        self.expect('[')?;
+        let mut value = String::new();
+
+        loop {
+            match self.next() {
+                Some(']') => return Ok(value),
+                Some('\\') => match self.next() {
+                    // An escaped newline is a soft line break and is removed
+                    Some('\n') => {
+                        if self.peek() == Some('\r') {
+                            self.next();
+                        }
+                    }
+                    Some('\r') => {
+                        if self.peek() == Some('\n') {
+                            self.next();
+                        }
+                    }
+                    Some(ch) => value.push(ch),
+                    None => return Err(SgfError::UnexpectedEnd),
+                },
+                Some(ch) => value.push(ch),
+                None => return Err(SgfError::UnexpectedEnd),
+            }
+        }
+    }
+}
+
+/// Converts an SGF point such as "pd" into board coordinates. SGF counts rows from the top of the
+/// board while the board counts them from the bottom, so rows are flipped. An empty value (or
+/// "tt" on boards up to 19x19) is a pass and returns `None`.
+pub fn parse_point(value: &str, size: usize) -> Result<Option<(usize, usize)>, SgfError> {
+    if value.is_empty() || (value == "tt" && size <= 19) {
+        return Ok(None);
+    }
+
+    let mut letters = value.chars().map(point_letter_index);
+    match (letters.next(), letters.next(), letters.next()) {
+        (Some(Some(col)), Some(Some(y)), None) if col < size && y < size => {
+            Ok(Some((size - 1 - y, col)))
+        }
+        _ => Err(SgfError::InvalidPoint(value.to_string())),
+    }
+}
+
+pub fn format_point(r: usize, c: usize, size: usize) -> String {
+    [c, size - 1 - r].into_iter().map(point_letter).collect()
+}
+
+/// Parses a list of points, expanding compressed rectangles such as "aa:cc". Empty values, which
+/// SGF uses for empty lists, are skipped.
+pub fn parse_point_list(values: &[String], size: usize) -> Result<Vec<(usize, usize)>, SgfError> {
+    let mut points = Vec::new();
+
+    for value in values.iter().filter(|value| !value.is_empty()) {
+        let invalid = || SgfError::InvalidPoint(value.clone());
+        match value.split_once(':') {
+            Some((first, second)) => {
+                let (r1, c1) = parse_point(first, size)?.ok_or_else(invalid)?;
+                let (r2, c2) = parse_point(second, size)?.ok_or_else(invalid)?;
+                for r in r1.min(r2)..=r1.max(r2) {
+                    for c in c1.min(c2)..=c1.max(c2) {
+                        points.push((r, c));
+                    }
+                }
+            }
+            None => points.push(parse_point(value, size)?.ok_or_else(invalid)?),
+        }
+    }
+
+    Ok(points)
+}
+
+fn point_letter_index(letter: char) -> Option<usize> {
+    match letter {
+        'a'..='z' => Some(letter as usize - 'a' as usize),
+        'A'..='Z' => Some(letter as usize - 'A' as usize + 26),
+        _ => None,
+    }
+}
+
+fn point_letter(index: usize) -> char {
+    if index < 26 {
+        (b'a' + index as u8) as char
+    } else {
+        (b'A' + (index - 26) as u8) as char
+    }
+}
+
+fn parse_point_pair(value: &str, size: usize) -> Result<PointPair, SgfError> {
+    let invalid = || SgfError::InvalidPoint(value.to_string());
+    let (first, second) = value.split_once(':').ok_or_else(invalid)?;
+    let first = parse_point(first, size)?.ok_or_else(invalid)?;
+    let second = parse_point(second, size)?.ok_or_else(invalid)?;
+    Ok((first, second))
+}
+
+/// Builds a game tree from an SGF game. Setup stones in the root node become the starting
+/// position, nodes without a move have their annotations merged into the preceding position.
+pub fn to_game_tree<const SIZE: usize>(
+    root: &SgfNode,
+) -> Result<GameTree<BadukClassical<SIZE>>, SgfError> {
+    let size = match root.get("SZ") {
+        Some(value) => value.trim().parse().unwrap_or(0),
+        None => 19,
+    };
+    if size != SIZE {
+        return Err(SgfError::BoardSize {
+            found: size,
+            expected: SIZE,
+        });
+    }
+
+    let mut board = Board::new();
+    for (r, c) in parse_point_list(root.get_all("AB"), SIZE)? {
+        board.place_stone(r, c, Player::Black);
+    }
+    for (r, c) in parse_point_list(root.get_all("AW"), SIZE)? {
+        board.place_stone(r, c, Player::White);
+    }
+
+    let turn = match root.get("PL").or_else(|| first_move_colour(root)) {
+        Some("W") => Player::White,
+        _ => Player::Black,
+    };
+
+    let mut tree = GameTree::new(BadukClassical::from_position(board, turn));
+    tree.info = read_game_info(root);
+    let root_node = tree.root_mut();
+    merge_annotations(&mut root_node.annotations, read_annotations(root, SIZE)?);
+    match read_move(root, SIZE)? {
+        Some((player, mv)) => {
+            root_node.game.turn = player;
+            add_children(root_node.make_move(mv)?, root)?
+        }
+        None => add_children(root_node, root)?,
+    }
+
+    Ok(tree)
+}
+
+fn add_children<const SIZE: usize>(
+    node: &mut GameNode<BadukClassical<SIZE>>,
+    sgf_node: &SgfNode,
+) -> Result<(), SgfError> {
+    for sgf_child in &sgf_node.children {
+        // Positions only change by moves below the root, the tree has no nodes for setup stones
+        if ["AB", "AW", "AE"].iter().any(|id| sgf_child.has(id)) {
+            return Err(SgfError::SetupOutsideRoot);
+        }
+        let annotations = read_annotations(sgf_child, SIZE)?;
+        match read_move(sgf_child, SIZE)? {
+            // The player may move twice in a row, common in problem collections
+            Some((player, mv)) => {
+                let mut game = node.game.clone();
+                game.turn = player;
+                game.make_move(mv)?;
+                // Children are keyed by the move alone, so the same point played by the other
+                // colour in a sibling variation would be taken for this one
+                if node
+                    .children
+                    .get(&mv)
+                    .is_some_and(|child| child.game != game)
+                {
+                    return Err(SgfError::ConflictingVariations);
+                }
+                let child = node
+                    .children
+                    .entry(mv)
+                    .or_insert_with(|| GameNode::new(game));
+                merge_annotations(&mut child.annotations, annotations);
+                add_children(child, sgf_child)?;
+            }
+            None => {
+                merge_annotations(&mut node.annotations, annotations);
+                add_children(node, sgf_child)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+fn read_game_info(root: &SgfNode) -> GameInfo {
+    let mut info = GameInfo::default();
+    for (id, field) in text_fields_mut(&mut info) {
+        *field = root.get(id).map(str::to_string);
+    }
+    // Records without KM were usually played without komi
+    info.komi = root
+        .get("KM")
+        .map_or(0.0, |komi| komi.trim().parse().unwrap_or(0.0));
+    info.handicap = root
+        .get("HA")
+        .and_then(|handicap| handicap.trim().parse().ok())
+        .unwrap_or(0);
+    info.main_time = root.get("TM").and_then(|time| time.trim().parse().ok());
+    // Unknown results such as "?" are left out
+    info.result = root.get("RE").and_then(|result| result.parse().ok());
+    info
+}
+
+fn text_fields_mut(info: &mut GameInfo) -> [(&'static str, &mut Option<String>); 10] {
+    [
+        ("PB", &mut info.black_player),
+        ("PW", &mut info.white_player),
+        ("BR", &mut info.black_rank),
+        ("WR", &mut info.white_rank),
+        ("EV", &mut info.event),
+        ("RO", &mut info.round),
+        ("DT", &mut info.date),
+        ("PC", &mut info.place),
+        ("RU", &mut info.rules),
+        ("OT", &mut info.overtime),
+    ]
+}
+
+fn text_fields(info: &GameInfo) -> [(&'static str, &Option<String>); 10] {
+    [
+        ("PB", &info.black_player),
+        ("PW", &info.white_player),
+        ("BR", &info.black_rank),
+        ("WR", &info.white_rank),
+        ("EV", &info.event),
+        ("RO", &info.round),
+        ("DT", &info.date),
+        ("PC", &info.place),
+        ("RU", &info.rules),
+        ("OT", &info.overtime),
+    ]
+}
+
+fn read_move(node: &SgfNode, size: usize) -> Result<Option<(Player, BadukMove)>, SgfError> {
+    let (player, value) = match (node.get("B"), node.get("W")) {
+        (Some(value), _) => (Player::Black, value),
+        (None, Some(value)) => (Player::White, value),
+        (None, None) => return Ok(None),
+    };
+    let mv = match parse_point(value, size)? {
+        Some(coordinates) => BadukMove::Play { coordinates },
+        None => BadukMove::Pass,
+    };
+    Ok(Some((player, mv)))
+}
+
+fn first_move_colour(node: &SgfNode) -> Option<&'static str> {
+    let child = node.children.first()?;
+    if child.has("B") {
+        Some("B")
+    } else if child.has("W") {
+        Some("W")
+    } else {
+        first_move_colour(child)
+    }
+}
+
+fn read_annotations(node: &SgfNode, size: usize) -> Result<NodeAnnotations, SgfError> {
+    let mut annotations = NodeAnnotations {
+        comment: node.get("C").map(str::to_string),
+        ..Default::default()
+    };
+
+    annotations.move_evaluation = [
+        ("TE", MoveEvaluation::Good),
+        ("BM", MoveEvaluation::Bad),
+        ("DO", MoveEvaluation::Doubtful),
+        ("IT", MoveEvaluation::Interesting),
+    ]
+    .into_iter()
+    .find_map(|(id, evaluation)| node.has(id).then_some(evaluation));
+
+    annotations.position_judgement = [
+        ("GB", PositionJudgement::GoodForBlack),
+        ("GW", PositionJudgement::GoodForWhite),
+        ("DM", PositionJudgement::Even),
+        ("UC", PositionJudgement::Unclear),
+    ]
+    .into_iter()
+    .find_map(|(id, judgement)| node.has(id).then_some(judgement));
+
+    let markup = &mut annotations.markup;
+    for point in parse_point_list(node.get_all("TR"), size)? {
+        markup.push(Markup::Triangle(point));
+    }
+    for point in parse_point_list(node.get_all("SQ"), size)? {
+        markup.push(Markup::Square(point));
+    }
+    for point in parse_point_list(node.get_all("CR"), size)? {
+        markup.push(Markup::Circle(point));
+    }
+    for point in parse_point_list(node.get_all("MA"), size)? {
+        markup.push(Markup::Cross(point));
+    }
+    for point in parse_point_list(node.get_all("DD"), size)? {
+        markup.push(Markup::Dimmed(point));
+    }
+
+    for value in node.get_all("LB") {
+        let (point, text) = value
+            .split_once(':')
+            .ok_or_else(|| SgfError::InvalidPoint(value.clone()))?;
+        let point =
+            parse_point(point, size)?.ok_or_else(|| SgfError::InvalidPoint(value.clone()))?;
+        markup.push(Markup::Label(point, text.to_string()));
+    }
+    for value in node.get_all("AR") {
+        let (from, to) = parse_point_pair(value, size)?;
+        markup.push(Markup::Arrow(from, to));
+    }
+    for value in node.get_all("LN") {
+        let (from, to) = parse_point_pair(value, size)?;
+        markup.push(Markup::Line(from, to));
+    }
+
+    Ok(annotations)
+}
+
+fn merge_annotations(annotations: &mut NodeAnnotations, other: NodeAnnotations) {
+    annotations.comment = match (annotations.comment.take(), other.comment) {
+        (Some(comment), Some(other)) => Some(format!("{comment}\n\n{other}")),
+        (comment, other) => comment.or(other),
+    };
+    annotations.move_evaluation = other.move_evaluation.or(annotations.move_evaluation);
+    annotations.position_judgement = other.position_judgement.or(annotations.position_judgement);
+    annotations.markup.extend(other.markup);
+}
+
+/// Converts a game tree into an SGF game, the inverse of `to_game_tree`.
+pub fn from_game_tree<const SIZE: usize>(tree: &GameTree<BadukClassical<SIZE>>) -> SgfNode {
+    let root = tree.root();
+    let mut sgf_root = SgfNode::default();
+    sgf_root.push("FF", "4");
+    sgf_root.push("GM", "1");
+    sgf_root.push("CA", "UTF-8");
+    sgf_root.push("SZ", SIZE.to_string());
+    write_game_info(&tree.info, &mut sgf_root);
+
+    for r in 0..SIZE {
+        for c in 0..SIZE {
+            match root.game.board.get_point(r, c) {
+                Some(Point::Stone(Player::Black)) => sgf_root.push("AB", format_point(r, c, SIZE)),
+                Some(Point::Stone(Player::White)) => sgf_root.push("AW", format_point(r, c, SIZE)),
+                _ => {}
+            }
+        }
+    }
+    if root.game.turn == Player::White {
+        sgf_root.push("PL", "W");
+    }
+
+    write_annotations(&root.annotations, SIZE, &mut sgf_root);
+    add_sgf_children(root, &mut sgf_root);
+    sgf_root
+}
+
+fn write_game_info(info: &GameInfo, root: &mut SgfNode) {
+    for (id, field) in text_fields(info) {
+        if let Some(value) = field {
+            root.push(id, value.clone());
+        }
+    }
+    root.push("KM", info.komi.to_string());
+    if info.handicap > 0 {
+        root.push("HA", info.handicap.to_string());
+    }
+    if let Some(main_time) = info.main_time {
+        root.push("TM", main_time.to_string());
+    }
+    if let Some(result) = info.result {
+        root.push("RE", result.to_string());
+    }
+}
+
+fn add_sgf_children<const SIZE: usize>(
+    node: &GameNode<BadukClassical<SIZE>>,
+    sgf_node: &mut SgfNode,
+) {
+    for (mv, child) in &node.children {
+        let mut sgf_child = SgfNode::default();
+        let colour = match child.game.turn.opponent() {
+            Player::Black => "B",
+            Player::White => "W",
+        };
+        let value = match mv {
+            BadukMove::Play {
+                coordinates: (r, c),
+            } => format_point(*r, *c, SIZE),
+            BadukMove::Pass => String::new(),
+            // SGF has no resignation move, the result in the header records it
+            BadukMove::Resign => continue,
+        };
+        sgf_child.push(colour, value);
+
+        write_annotations(&child.annotations, SIZE, &mut sgf_child);
+        add_sgf_children(child, &mut sgf_child);
+        sgf_node.children.push(sgf_child);
+    }
+}
+
+fn write_annotations(annotations: &NodeAnnotations, size: usize, node: &mut SgfNode) {
+    if let Some(comment) = &annotations.comment {
+        node.push("C", comment.clone());
+    }
+
+    match annotations.move_evaluation {
+        Some(MoveEvaluation::Good) => node.push("TE", "1"),
+        Some(MoveEvaluation::Bad) => node.push("BM", "1"),
+        Some(MoveEvaluation::Doubtful) => node.push("DO", ""),
+        Some(MoveEvaluation::Interesting) => node.push("IT", ""),
+        None => {}
+    }
+
+    match annotations.position_judgement {
+        Some(PositionJudgement::GoodForBlack) => node.push("GB", "1"),
+        Some(PositionJudgement::GoodForWhite) => node.push("GW", "1"),
+        Some(PositionJudgement::Even) => node.push("DM", "1"),
+        Some(PositionJudgement::Unclear) => node.push("UC", "1"),
+        None => {}
+    }
+
+    let point = |(r, c): (usize, usize)| format_point(r, c, size);
+    for markup in &annotations.markup {
+        match markup {
+            Markup::Triangle(p) => node.push("TR", point(*p)),
+            Markup::Square(p) => node.push("SQ", point(*p)),
+            Markup::Circle(p) => node.push("CR", point(*p)),
+            Markup::Cross(p) => node.push("MA", point(*p)),
+            Markup::Dimmed(p) => node.push("DD", point(*p)),
+            Markup::Label(p, text) => node.push("LB", format!("{}:{text}", point(*p))),
+            Markup::Arrow(from, to) => node.push("AR", format!("{}:{}", point(*from), point(*to))),
+            Markup::Line(from, to) => node.push("LN", format!("{}:{}", point(*from), point(*to))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn moves_take_their_colour_from_the_record() {
+        let collection = parse("(;SZ[9];B[aa];B[bb];W[cc])").unwrap();
+        let tree = to_game_tree::<9>(&collection[0]).unwrap();
+        let (_, last) = tree.main_line().last().unwrap();
+
+        assert_eq!(
+            last.game.board.get_point(8, 0),
+            Some(Point::Stone(Player::Black))
+        );
+        assert_eq!(
+            last.game.board.get_point(7, 1),
+            Some(Point::Stone(Player::Black))
+        );
+        assert_eq!(
+            last.game.board.get_point(6, 2),
+            Some(Point::Stone(Player::White))
+        );
+        assert_eq!(last.game.turn, Player::Black);
+    }
+
+    #[test]
+    fn setup_stones_below_the_root_are_rejected() {
+        let collection = parse("(;SZ[9];B[aa];AW[bb])").unwrap();
+        assert!(matches!(
+            to_game_tree::<9>(&collection[0]),
+            Err(SgfError::SetupOutsideRoot)
+        ));
+    }
+
+    #[test]
+    fn variations_playing_the_same_point_with_both_colours_are_rejected() {
+        let collection = parse("(;SZ[9];B[aa](;W[bb])(;B[bb]))").unwrap();
+        assert!(matches!(
+            to_game_tree::<9>(&collection[0]),
+            Err(SgfError::ConflictingVariations)
+        ));
+
+        let collection = parse("(;SZ[9];B[aa](;W[bb]C[first])(;W[bb]C[again]))").unwrap();
+        let tree = to_game_tree::<9>(&collection[0]).unwrap();
+        assert_eq!(tree.root().main_child().unwrap().1.children.len(), 1);
+    }
+}
@@ -0,0 +1,337 @@
+use std::collections::HashMap;
+
+use crate::game::{Player, StatelessGame};
+
+/// The value of a won position. Heuristic values stay well inside it, so a value of `WIN` or
+/// `-WIN` is always proven.
+pub const WIN: i32 = 1_000_000;
+/// The largest value a heuristic may give.
+pub const HEURISTIC_LIMIT: i32 = WIN / 2;
+
+// Nodes between asking whether to stop
+const STOP_CHECK_INTERVAL: u64 = 1024;
+
+/// Judges positions where the search runs out of depth, for the player to move.
+pub trait SolverHeuristic<G: StatelessGame> {
+    /// A value between `-HEURISTIC_LIMIT` and `HEURISTIC_LIMIT`, higher being better for the
+    /// player to move.
+    fn evaluate(&self, game: &G, komi: f32) -> i32;
+}
+
+/// Counts the position as if the game ended now.
+#[derive(Clone, Copy, Default, Debug)]
+pub struct WinnerHeuristic;
+
+impl<G: StatelessGame> SolverHeuristic<G> for WinnerHeuristic {
+    fn evaluate(&self, game: &G, komi: f32) -> i32 {
+        outcome_value(game.winner(komi), game.turn(), 1)
+    }
+}
+
+/// The result of a solved position for the player to move.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Outcome {
+    Win,
+    Loss,
+    Draw,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct SolveResult<M> {
+    pub best_move: Option<M>,
+    /// From the point of view of the player to move.
+    pub value: i32,
+    /// Set once the search has proven the result with best play from both sides.
+    pub proven: Option<Outcome>,
+    /// The depth of the last completed iteration.
+    pub depth: u32,
+    pub nodes: u64,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum Bound {
+    Exact,
+    Lower,
+    Upper,
+}
+
+#[derive(Clone, Copy, Debug)]
+struct SolverEntry<M> {
+    value: i32,
+    bound: Bound,
+    depth: u32,
+    // Whether the whole subtree was searched to the end of the game, so the value holds at any
+    // depth
+    complete: bool,
+    best_move: Option<M>,
+}
+
+/// Iterative deepening negamax with alpha-beta pruning, a transposition table and move ordering
+/// by the table's best move and the history heuristic.
+///
+/// Positions are told apart by `position_hash`. For games whose rules look at earlier positions,
+/// such as superko, two positions with the same hash can play differently, which can rarely make
+/// a result wrong.
+pub struct Solver<G: StatelessGame, H = WinnerHeuristic> {
+    heuristic: H,
+    table: HashMap<u64, SolverEntry<G::Move>>,
+    // The komi the table's values were found with
+    table_komi: Option<f32>,
+    max_table_size: usize,
+    history: HashMap<G::Move, u32>,
+    nodes: u64,
+    stopped: bool,
+}
+
+impl<G: StatelessGame> Solver<G> {
+    pub fn new(max_table_size: usize) -> Self {
+        Self::with_heuristic(WinnerHeuristic, max_table_size)
+    }
+}
+
+impl<G: StatelessGame, H: SolverHeuristic<G>> Solver<G, H> {
+    pub fn with_heuristic(heuristic: H, max_table_size: usize) -> Self {
+        Solver {
+            heuristic,
+            table: HashMap::new(),
+            table_komi: None,
+            max_table_size,
+            history: HashMap::new(),
+            nodes: 0,
+            stopped: false,
+        }
+    }
+
+    /// Searches one move deeper at a time until the result is proven, `max_depth` is reached or
+    /// `should_stop` says so. An interrupted iteration is thrown away. The table is kept for the
+    /// next search unless the komi changes.
+    pub fn solve(
+        &mut self,
+        game: &G,
+        komi: f32,
+        max_depth: u32,
+        should_stop: impl Fn() -> bool,
+    ) -> SolveResult<G::Move> {
+        self.nodes = 0;
+        self.stopped = false;
+        self.history.clear();
+        if self.table_komi != Some(komi) {
+            self.table.clear();
+            self.table_komi = Some(komi);
+        }
+        let mut result = SolveResult {
+            best_move: game.list_all_legal_moves().first().copied(),
+            value: 0,
+            proven: None,
+            depth: 0,
+            nodes: 0,
+        };
+
+        for depth in 1..=max_depth {
+            let (value, complete) = self.negamax(game, komi, depth, -WIN, WIN, &should_stop);
+            if self.stopped {
+                break;
+            }
+            result.value = value;
+            result.depth = depth;
+            if let Some(best_move) = self
+                .table
+                .get(&game.position_hash())
+                .and_then(|entry| entry.best_move)
+            {
+                result.best_move = Some(best_move);
+            }
+            result.proven = if value == WIN {
+                Some(Outcome::Win)
+            } else if value == -WIN {
+                Some(Outcome::Loss)
+            } else if value == 0 && complete {
+                Some(Outcome::Draw)
+            } else {
+                None
+            };
+            if result.proven.is_some() || game.is_game_over() {
+                break;
+            }
+        }
+        result.nodes = self.nodes;
+        result
+    }
+
+    /// The value of the position for the player to move and whether every line was followed to
+    /// the end of the game.
+    fn negamax(
+        &mut self,
+        game: &G,
+        komi: f32,
+        depth: u32,
+        mut alpha: i32,
+        mut beta: i32,
+        should_stop: &impl Fn() -> bool,
+    ) -> (i32, bool) {
+        self.nodes += 1;
+        if self.nodes.is_multiple_of(STOP_CHECK_INTERVAL) && should_stop() {
+            self.stopped = true;
+        }
+        if self.stopped {
+            return (0, false);
+        }
+        if game.is_game_over() {
+            return (outcome_value(game.winner(komi), game.turn(), WIN), true);
+        }
+        if depth == 0 {
+            return (self.heuristic.evaluate(game, komi), false);
+        }
+
+        let hash = game.position_hash();
+        let original_alpha = alpha;
+        let mut table_move = None;
+        if let Some(entry) = self.table.get(&hash) {
+            table_move = entry.best_move;
+            if entry.complete || entry.depth >= depth {
+                match entry.bound {
+                    Bound::Exact => return (entry.value, entry.complete),
+                    Bound::Lower => alpha = alpha.max(entry.value),
+                    Bound::Upper => beta = beta.min(entry.value),
+                }
+                if alpha >= beta {
+                    return (entry.value, entry.complete);
+                }
+            }
+        }
+
+        let mut moves = game.list_all_legal_moves();
+        moves.sort_by_key(|mv| {
+            (
+                Some(*mv) != table_move,
+                std::cmp::Reverse(self.history.get(mv).copied().unwrap_or(0)),
+            )
+        });
+
+        let mut best_value = -WIN - 1;
+        let mut best_move = None;
+        let mut complete = true;
+        for mv in moves {
+            let Ok(next) = game.generate_next_board(&mv) else {
+                continue;
+            };
+            let (child_value, child_complete) =
+                self.negamax(&next, komi, depth - 1, -beta, -alpha, should_stop);
+            if self.stopped {
+                return (0, false);
+            }
+            let value = -child_value;
+            complete &= child_complete;
+            if value > best_value {
+                best_value = value;
+                best_move = Some(mv);
+            }
+            alpha = alpha.max(value);
+            if alpha >= beta {
+                *self.history.entry(mv).or_insert(0) += depth * depth;
+                break;
+            }
+        }
+
+        if best_move.is_none() {
+            return (self.heuristic.evaluate(game, komi), false);
+        }
+        let bound = if best_value <= original_alpha {
+            Bound::Upper
+        } else if best_value >= beta {
+            Bound::Lower
+        } else {
+            Bound::Exact
+        };
+        if self.table.len() >= self.max_table_size {
+            self.table.clear();
+        }
+        self.table.insert(
+            hash,
+            SolverEntry {
+                value: best_value,
+                bound,
+                depth,
+                complete,
+                best_move,
+            },
+        );
+        (best_value, complete)
+    }
+}
+
+fn outcome_value(winner: Option<Player>, to_move: Player, win: i32) -> i32 {
+    match winner {
+        Some(winner) if winner == to_move => win,
+        Some(_) => -win,
+        None => 0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bot::GameBot;
+    use crate::game::{BadukClassical, BadukMove};
+    use crate::search::SearchContext;
+    use crate::solver_bot::{SolverBot, SolverConfig};
+
+    fn solve<const SIZE: usize>(
+        solver: &mut Solver<BadukClassical<SIZE>>,
+        komi: f32,
+    ) -> SolveResult<BadukMove> {
+        solver.solve(&BadukClassical::new(), komi, 64, || false)
+    }
+
+    #[test]
+    fn one_point_board_is_a_draw() {
+        let result = solve(&mut Solver::<BadukClassical<1>>::new(1 << 16), 0.0);
+        assert_eq!(result.proven, Some(Outcome::Draw));
+        assert_eq!(result.best_move, Some(BadukMove::Pass));
+    }
+
+    // 2x2 is won by Black by one point
+    #[test]
+    fn two_by_two_board_is_won_by_one_point() {
+        let mut solver = Solver::<BadukClassical<2>>::new(1 << 16);
+        let result = solve(&mut solver, 0.5);
+        assert_eq!(result.proven, Some(Outcome::Win));
+        assert_eq!(result.value, WIN);
+
+        // The same solver again, the table of the first search must not carry over
+        let result = solve(&mut solver, 1.5);
+        assert_eq!(result.proven, Some(Outcome::Loss));
+        assert_eq!(result.value, -WIN);
+    }
+
+    // 3x3 is won by Black by the whole board, which only the centre opening achieves
+    #[test]
+    fn three_by_three_board_is_won_by_the_centre() {
+        let result = solve(&mut Solver::<BadukClassical<3>>::new(1 << 20), 8.5);
+        assert_eq!(result.proven, Some(Outcome::Win));
+        assert_eq!(
+            result.best_move,
+            Some(BadukMove::Play {
+                coordinates: (1, 1)
+            })
+        );
+    }
+
+    #[test]
+    fn solver_bot_searches_again_when_the_komi_changes() {
+        let bot = SolverBot::<BadukClassical<2>>::from_config(SolverConfig::default());
+        let game = BadukClassical::new();
+        for (komi, outcome) in [
+            (0.5, Outcome::Win),
+            (1.5, Outcome::Loss),
+            (0.5, Outcome::Win),
+        ] {
+            let context = SearchContext {
+                komi,
+                ..SearchContext::new(2)
+            };
+            bot.select_move(&game, &context).unwrap();
+            assert_eq!(bot.last_result().unwrap().proven, Some(outcome));
+        }
+    }
+}
@@ -0,0 +1,110 @@
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::bot::{parse_option, BotConfig, BotError, GameBot};
+use crate::game::{MoveError, StatelessGame};
+use crate::search::SearchContext;
+use crate::solver::{SolveResult, Solver};
+
+#[derive(Clone, Debug)]
+pub struct SolverConfig {
+    /// The deepest iteration, in moves.
+    pub max_depth: u32,
+    /// How long to think in games without a clock.
+    pub untimed_budget: Duration,
+    /// The most positions the transposition table holds before it starts over.
+    pub table_size: usize,
+}
+
+impl Default for SolverConfig {
+    fn default() -> Self {
+        SolverConfig {
+            max_depth: 64,
+            untimed_budget: Duration::from_secs(10),
+            table_size: 1 << 20,
+        }
+    }
+}
+
+impl BotConfig for SolverConfig {
+    fn options(&self) -> Vec<(&'static str, String)> {
+        vec![
+            ("depth", self.max_depth.to_string()),
+            ("time", self.untimed_budget.as_secs_f32().to_string()),
+            ("table", self.table_size.to_string()),
+        ]
+    }
+
+    fn set_option(&mut self, name: &str, value: &str) -> Result<(), BotError> {
+        match name {
+            "depth" => self.max_depth = parse_option(name, value)?,
+            "time" => {
+                let seconds: f32 = parse_option(name, value)?;
+                self.untimed_budget =
+                    Duration::try_from_secs_f32(seconds).map_err(|_| BotError::InvalidValue {
+                        name: name.to_string(),
+                        value: value.to_string(),
+                    })?;
+            }
+            "table" => self.table_size = parse_option(name, value)?,
+            _ => return Err(BotError::UnknownOption(name.to_string())),
+        }
+        Ok(())
+    }
+}
+
+/// Plays the move of an alpha-beta search. On boards small enough to be solved within the time
+/// for a move it plays perfectly, otherwise it plays the best move of the deepest search it
+/// finished.
+#[derive(Clone)]
+pub struct SolverBot<G: StatelessGame> {
+    config: SolverConfig,
+    // Kept between moves, so the table still holds the positions of the last search
+    solver: Arc<Mutex<Solver<G>>>,
+    last_result: Arc<Mutex<Option<SolveResult<G::Move>>>>,
+}
+
+impl<G: StatelessGame> SolverBot<G> {
+    /// The result of the last search, including whether it was proven.
+    pub fn last_result(&self) -> Option<SolveResult<G::Move>> {
+        self.last_result
+            .lock()
+            .unwrap_or_else(|err| err.into_inner())
+            .clone()
+    }
+}
+
+impl<G: StatelessGame> GameBot for SolverBot<G> {
+    type Game = G;
+    type Config = SolverConfig;
+    fn from_config(config: SolverConfig) -> Self {
+        let solver = Solver::new(config.table_size);
+        SolverBot {
+            config,
+            solver: Arc::new(Mutex::new(solver)),
+            last_result: Arc::default(),
+        }
+    }
+
+    fn config(&self) -> &SolverConfig {
+        &self.config
+    }
+
+    fn select_move(&self, game: &G, context: &SearchContext) -> Result<G::Move, MoveError> {
+        let budget = context.time_budget().unwrap_or(self.config.untimed_budget);
+        let started = Instant::now();
+        let result = self
+            .solver
+            .lock()
+            .unwrap_or_else(|err| err.into_inner())
+            .solve(game, context.komi, self.config.max_depth, || {
+                context.stop_requested() || started.elapsed() >= budget
+            });
+        let best_move = result.best_move.ok_or(MoveError::NoLegalMoves);
+        *self
+            .last_result
+            .lock()
+            .unwrap_or_else(|err| err.into_inner()) = Some(result);
+        best_move
+    }
+}
@@ -0,0 +1,193 @@
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Mutex;
+
+// Entries per bucket, a new position replaces the least useful one of its bucket
+const BUCKET_SIZE: usize = 2;
+
+/// What the search found out about one position, whichever moves led to it.
+#[derive(Clone, Copy, Default, Debug, PartialEq)]
+pub struct PositionStats {
+    pub visits: u32,
+    /// Playouts won by the player who moved into the position, a draw counts as half.
+    pub wins: f32,
+}
+
+#[derive(Clone, Copy, Debug)]
+struct TableEntry {
+    hash: u64,
+    stats: PositionStats,
+    // The search that last recorded a playout here
+    generation: u32,
+}
+
+/// Search statistics keyed by position hash, so that positions reached by different move orders
+/// share them. The table holds a fixed number of positions. When a bucket is full, positions not
+/// seen in the current search are replaced first, then the one with the fewest visits.
+pub struct TranspositionTable {
+    buckets: Vec<Mutex<[Option<TableEntry>; BUCKET_SIZE]>>,
+    generation: AtomicU32,
+}
+
+impl TranspositionTable {
+    /// A table for about `capacity` positions.
+    pub fn new(capacity: usize) -> Self {
+        let bucket_count = capacity.div_ceil(BUCKET_SIZE).max(1);
+        TranspositionTable {
+            buckets: (0..bucket_count)
+                .map(|_| Mutex::new([None; BUCKET_SIZE]))
+                .collect(),
+            generation: AtomicU32::new(0),
+        }
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.buckets.len() * BUCKET_SIZE
+    }
+
+    /// Marks the positions recorded so far as left over from earlier searches, so they make room
+    /// for the ones the next search finds. Their statistics stay until they are replaced.
+    pub fn new_search(&self) {
+        self.generation.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn clear(&self) {
+        for bucket in &self.buckets {
+            *bucket.lock().unwrap_or_else(|err| err.into_inner()) = [None; BUCKET_SIZE];
+        }
+    }
+
+    pub fn get(&self, hash: u64) -> Option<PositionStats> {
+        self.bucket(hash)
+            .lock()
+            .unwrap_or_else(|err| err.into_inner())
+            .iter()
+            .flatten()
+            .find(|entry| entry.hash == hash)
+            .map(|entry| entry.stats)
+    }
+
+    /// Counts one playout through the position, `wins` being 1 for a win of the player who moved
+    /// into it, 0.5 for a draw and 0 for a loss.
+    pub fn record(&self, hash: u64, wins: f32) {
+        let generation = self.generation.load(Ordering::Relaxed);
+        let mut bucket = self
+            .bucket(hash)
+            .lock()
+            .unwrap_or_else(|err| err.into_inner());
+
+        let slot = match bucket
+            .iter()
+            .position(|entry| entry.is_some_and(|entry| entry.hash == hash))
+        {
+            Some(slot) => slot,
+            None => {
+                let slot = replacement_slot(&bucket, generation);
+                bucket[slot] = Some(TableEntry {
+                    hash,
+                    stats: PositionStats::default(),
+                    generation,
+                });
+                slot
+            }
+        };
+        let entry = bucket[slot].as_mut().expect("The slot was just filled");
+        entry.stats.visits += 1;
+        entry.stats.wins += wins;
+        entry.generation = generation;
+    }
+
+    fn bucket(&self, hash: u64) -> &Mutex<[Option<TableEntry>; BUCKET_SIZE]> {
+        &self.buckets[(hash % self.buckets.len() as u64) as usize]
+    }
+}
+
+/// An empty slot if there is one, otherwise the entry with the fewest visits, taken from earlier
+/// searches first.
+fn replacement_slot(bucket: &[Option<TableEntry>; BUCKET_SIZE], generation: u32) -> usize {
+    bucket
+        .iter()
+        .enumerate()
+        .min_by_key(|(_, entry)| match entry {
+            None => (0, 0),
+            Some(entry) => (
+                1 + u64::from(entry.generation == generation),
+                entry.stats.visits,
+            ),
+        })
+        .map_or(0, |(slot, _)| slot)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::{BadukClassical, BadukMove, StatelessGame};
+
+    #[test]
+    fn playouts_add_up_per_position() {
+        let table = TranspositionTable::new(64);
+        table.record(7, 1.0);
+        table.record(7, 0.5);
+        table.record(9, 0.0);
+
+        assert_eq!(
+            table.get(7),
+            Some(PositionStats {
+                visits: 2,
+                wins: 1.5
+            })
+        );
+        assert_eq!(table.get(9).map(|stats| stats.visits), Some(1));
+        assert_eq!(table.get(8), None);
+    }
+
+    #[test]
+    fn a_full_bucket_replaces_the_least_visited_position() {
+        // A single bucket, so every position competes for the same slots
+        let table = TranspositionTable::new(BUCKET_SIZE);
+        table.record(1, 1.0);
+        table.record(1, 1.0);
+        table.record(2, 1.0);
+        table.record(3, 1.0);
+
+        assert!(table.get(1).is_some());
+        assert!(table.get(2).is_none());
+        assert!(table.get(3).is_some());
+    }
+
+    #[test]
+    fn positions_of_earlier_searches_are_replaced_first() {
+        let table = TranspositionTable::new(BUCKET_SIZE);
+        for _ in 0..10 {
+            table.record(1, 1.0);
+        }
+        table.new_search();
+        table.record(2, 1.0);
+        table.record(3, 1.0);
+
+        assert!(table.get(1).is_none());
+        assert!(table.get(2).is_some());
+        assert!(table.get(3).is_some());
+    }
+
+    #[test]
+    fn transposed_move_orders_share_an_entry() {
+        let play = |r, c| BadukMove::Play {
+            coordinates: (r, c),
+        };
+        let line = |moves: [BadukMove; 3]| {
+            moves
+                .iter()
+                .try_fold(BadukClassical::<9>::new(), |game, mv| {
+                    game.generate_next_board(mv)
+                })
+                .unwrap()
+        };
+        let first = line([play(2, 2), play(6, 6), play(2, 6)]);
+        let second = line([play(2, 6), play(6, 6), play(2, 2)]);
+
+        let table = TranspositionTable::new(64);
+        table.record(first.position_hash(), 1.0);
+        table.record(second.position_hash(), 0.0);
+        assert_eq!(table.get(first.position_hash()).map(|s| s.visits), Some(2));
+    }
+}
@@ -0,0 +1,261 @@
+use crate::game::{BadukClassical, BadukMove, GameNode, GameTree, MoveEvaluation};
+use crate::sgf::{self, SgfError, SgfNode};
+use indexmap::IndexMap;
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+
+// Problem files follow the common convention of marking the end of every correct variation with a
+// comment containing "RIGHT" (or "Correct"), or with the SGF tesuji property TE. Any other leaf is a
+// wrong answer. Collections that mark nothing at all only contain correct variations, so every
+// leaf counts as solved there.
+
+const PROGRESS_FILE_NAME: &str = ".badukrs-progress";
+
+#[derive(Error, Debug)]
+pub enum TsumegoError {
+    #[error("Could not read {path}: {source}")]
+    Io {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    #[error("Could not load {path}: {source}")]
+    Sgf { path: PathBuf, source: SgfError },
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AttemptOutcome {
+    /// The move was in the problem tree, the opponent answered with `reply` (if any).
+    Continue {
+        reply: Option<BadukMove>,
+    },
+    Solved,
+    Failed,
+}
+
+pub struct Problem<const SIZE: usize> {
+    pub name: String,
+    pub tree: GameTree<BadukClassical<SIZE>>,
+    correct_lines: HashSet<Vec<BadukMove>>,
+    marks_correct_lines: bool,
+}
+
+impl<const SIZE: usize> Problem<SIZE> {
+    pub fn from_sgf(name: String, sgf_root: &SgfNode) -> Result<Self, SgfError> {
+        let tree = sgf::to_game_tree(sgf_root)?;
+        let mut correct_lines = HashSet::new();
+        collect_correct_lines(tree.root(), &mut Vec::new(), &mut correct_lines);
+
+        Ok(Problem {
+            name,
+            tree,
+            marks_correct_lines: !correct_lines.is_empty(),
+            correct_lines,
+        })
+    }
+
+    pub fn node(&self, line: &[BadukMove]) -> Option<&GameNode<BadukClassical<SIZE>>> {
+        self.tree.node(line).ok()
+    }
+
+    /// Plays the player's move along `line`, auto-playing the opponent's main reply.
+    pub fn respond(&self, line: &mut Vec<BadukMove>, mv: BadukMove) -> AttemptOutcome {
+        let in_tree = self
+            .node(line)
+            .is_some_and(|node| node.children.contains_key(&mv));
+        if !in_tree {
+            return AttemptOutcome::Failed;
+        }
+        line.push(mv);
+        if let Some(outcome) = self.judge(line) {
+            return outcome;
+        }
+
+        let reply = self
+            .node(line)
+            .and_then(|node| node.children.keys().next().copied());
+        if let Some(reply) = reply {
+            line.push(reply);
+            if let Some(outcome) = self.judge(line) {
+                return outcome;
+            }
+        }
+
+        AttemptOutcome::Continue { reply }
+    }
+
+    fn judge(&self, line: &[BadukMove]) -> Option<AttemptOutcome> {
+        if self.correct_lines.contains(line) {
+            return Some(AttemptOutcome::Solved);
+        }
+
+        let is_leaf = self.node(line).is_some_and(|node| node.children.is_empty());
+        match (is_leaf, self.marks_correct_lines) {
+            (false, _) => None,
+            (true, true) => Some(AttemptOutcome::Failed),
+            (true, false) => Some(AttemptOutcome::Solved),
+        }
+    }
+}
+
+fn collect_correct_lines<const SIZE: usize>(
+    node: &GameNode<BadukClassical<SIZE>>,
+    line: &mut Vec<BadukMove>,
+    correct_lines: &mut HashSet<Vec<BadukMove>>,
+) {
+    if is_marked_correct(node) {
+        correct_lines.insert(line.clone());
+    }
+
+    for (mv, child) in &node.children {
+        line.push(*mv);
+        collect_correct_lines(child, line, correct_lines);
+        line.pop();
+    }
+}
+
+fn is_marked_correct<const SIZE: usize>(node: &GameNode<BadukClassical<SIZE>>) -> bool {
+    let annotations = &node.annotations;
+    annotations.move_evaluation == Some(MoveEvaluation::Good)
+        || annotations
+            .comment
+            .as_ref()
+            .is_some_and(|comment| comment.contains("RIGHT") || comment.contains("Correct"))
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ProblemStatus {
+    Unattempted,
+    Failed,
+    Solved,
+}
+
+pub struct ProblemSet<const SIZE: usize> {
+    pub directory: PathBuf,
+    pub problems: Vec<Problem<SIZE>>,
+    /// The problems left out because they are on another board size.
+    pub skipped: Vec<TsumegoError>,
+    progress: IndexMap<String, ProblemStatus>,
+}
+
+impl<const SIZE: usize> ProblemSet<SIZE> {
+    /// Loads every `.sgf` file in `directory`, in file name order. A file containing a collection
+    /// of several game trees yields one problem per tree. Problems on another board size are
+    /// skipped and listed in `skipped`.
+    pub fn load(directory: &Path) -> Result<Self, TsumegoError> {
+        let io_error = |path: &Path| {
+            let path = path.to_path_buf();
+            move |source| TsumegoError::Io { path, source }
+        };
+
+        let paths = sgf::sgf_files_in(directory).map_err(io_error(directory))?;
+
+        let mut problems = Vec::new();
+        let mut skipped = Vec::new();
+        for path in paths {
+            let contents = fs::read_to_string(&path).map_err(io_error(&path))?;
+            let collection = sgf::parse(&contents).map_err(|source| TsumegoError::Sgf {
+                path: path.clone(),
+                source,
+            })?;
+
+            let stem = path
+                .file_stem()
+                .map(|stem| stem.to_string_lossy().into_owned())
+                .unwrap_or_default();
+            for (index, sgf_root) in collection.iter().enumerate() {
+                let name = if collection.len() == 1 {
+                    stem.clone()
+                } else {
+                    format!("{stem}#{}", index + 1)
+                };
+                match Problem::from_sgf(name, sgf_root) {
+                    Ok(problem) => problems.push(problem),
+                    Err(source @ SgfError::BoardSize { .. }) => skipped.push(TsumegoError::Sgf {
+                        path: path.clone(),
+                        source,
+                    }),
+                    Err(source) => {
+                        return Err(TsumegoError::Sgf {
+                            path: path.clone(),
+                            source,
+                        })
+                    }
+                }
+            }
+        }
+
+        let mut set = ProblemSet {
+            directory: directory.to_path_buf(),
+            problems,
+            skipped,
+            progress: IndexMap::new(),
+        };
+        set.load_progress();
+        Ok(set)
+    }
+
+    pub fn status(&self, index: usize) -> ProblemStatus {
+        self.problems
+            .get(index)
+            .and_then(|problem| self.progress.get(&problem.name))
+            .copied()
+            .unwrap_or(ProblemStatus::Unattempted)
+    }
+
+    pub fn solved_count(&self) -> usize {
+        (0..self.problems.len())
+            .filter(|&index| self.status(index) == ProblemStatus::Solved)
+            .count()
+    }
+
+    /// Records an attempt and saves the progress file. A solved problem stays solved.
+    pub fn record(&mut self, index: usize, status: ProblemStatus) -> Result<(), TsumegoError> {
+        if self.status(index) == ProblemStatus::Solved {
+            return Ok(());
+        }
+        if let Some(problem) = self.problems.get(index) {
+            self.progress.insert(problem.name.clone(), status);
+        }
+        self.save_progress()
+    }
+
+    fn progress_path(&self) -> PathBuf {
+        self.directory.join(PROGRESS_FILE_NAME)
+    }
+
+    // The progress file has one "name<TAB>status" line per attempted problem
+    fn load_progress(&mut self) {
+        let Ok(contents) = fs::read_to_string(self.progress_path()) else {
+            return;
+        };
+
+        for line in contents.lines() {
+            let Some((name, status)) = line.rsplit_once('\t') else {
+                continue;
+            };
+            let status = match status {
+                "solved" => ProblemStatus::Solved,
+                "failed" => ProblemStatus::Failed,
+                _ => continue,
+            };
+            self.progress.insert(name.to_string(), status);
+        }
+    }
+
+    fn save_progress(&self) -> Result<(), TsumegoError> {
+        let mut contents = String::new();
+        for (name, status) in &self.progress {
+            let status = match status {
+                ProblemStatus::Solved => "solved",
+                ProblemStatus::Failed => "failed",
+                ProblemStatus::Unattempted => continue,
+            };
+            contents.push_str(&format!("{name}\t{status}\n"));
+        }
+
+        let path = self.progress_path();
+        fs::write(&path, contents).map_err(|source| TsumegoError::Io { path, source })
+    }
+}